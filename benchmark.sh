@@ -1,6 +1,2 @@
 #!/bin/bash
-rm -f ./benchmark.txt
-for day in {1..25}
-do
-    cargo run --release $day >> benchmark.txt
-done
\ No newline at end of file
+cargo run --release -- all --bench --save --update-readme > benchmark.txt
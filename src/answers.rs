@@ -0,0 +1,338 @@
+//! Known answers, used to check the solutions still give the right results
+//!
+//! Answers live in a TOML file alongside the inputs, with a table per day,
+//!
+//! ```toml
+//! [day01]
+//! part1 = 67027
+//! part2 = "197291"
+//!
+//! [day10]
+//! part2 = """
+//! #..#
+//! ####
+//! """
+//! ```
+//!
+//! Only the subset of TOML needed for this is understood: `[dayNN]` headers,
+//! `part1` and `part2` keys, and integer (of any size), string or multi-line
+//! string values. Comments start with `#`.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::error::ParseError;
+use crate::parse::{integer, normalise};
+use crate::solution::Report;
+
+/// The expected answer to each part of each day, where known
+#[derive(Debug, Default)]
+pub struct Answers {
+    expected: HashMap<(u8, u8), String>,
+}
+
+impl Answers {
+    /// Returns the answers described by a TOML file
+    pub fn parse(input: &str) -> Result<Self, ParseError> {
+        let input = &normalise(input);
+
+        let mut answers = Answers::default();
+        let mut day = None;
+        let mut lines = input.lines();
+
+        while let Some(line) = lines.next() {
+            let line = _strip_comment(line).trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            // Table headers name the day the following answers belong to
+            if let Some(header) = line.strip_prefix('[') {
+                let name = header
+                    .strip_suffix(']')
+                    .ok_or_else(|| ParseError::after(input, line, "`]`"))?
+                    .trim();
+                let number = name
+                    .strip_prefix("day")
+                    .ok_or_else(|| ParseError::at(input, name, "a table named `dayNN`"))?;
+                let number = integer::<u8>(input, number)?;
+                if !(1..=25).contains(&number) {
+                    return Err(ParseError::at(input, name, "a day from 1 to 25"));
+                }
+
+                day = Some(number);
+                continue;
+            }
+
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| ParseError::at(input, line, "`[dayNN]` or `partN = answer`"))?;
+            let (key, value) = (key.trim(), value.trim());
+
+            let part = match key {
+                "part1" => 1,
+                "part2" => 2,
+                _ => return Err(ParseError::at(input, key, "`part1` or `part2`")),
+            };
+            let day = day.ok_or_else(|| ParseError::at(input, key, "a `[dayNN]` header first"))?;
+
+            let value = match value.strip_prefix("\"\"\"") {
+                Some(rest) => _multiline_string(input, rest, &mut lines)?,
+                None if value.starts_with('"') => _basic_string(input, value)?,
+                None => _integer(input, value)?.to_string(),
+            };
+
+            if answers.expected.insert((day, part), value).is_some() {
+                return Err(ParseError::at(input, key, "each part to be given only once"));
+            }
+        }
+
+        Ok(answers)
+    }
+
+    /// Returns the expected answer to a part of a day, if known
+    pub fn get(&self, day: u8, part: u8) -> Option<&str> {
+        self.expected.get(&(day, part)).map(String::as_str)
+    }
+
+    /// Compares each answer in a report with the expected one
+    pub fn check(&self, day: u8, report: &Report) -> Vec<Check> {
+        report
+            .answers
+            .iter()
+            .map(|answer| {
+                let outcome = match self.get(day, answer.part) {
+                    None => Outcome::Unknown,
+                    Some(expected) if expected == answer.value => Outcome::Correct,
+                    Some(expected) => Outcome::Wrong {
+                        expected: expected.to_string(),
+                        found: answer.value.clone(),
+                    },
+                };
+
+                Check { day, part: answer.part, outcome }
+            })
+            .collect()
+    }
+}
+
+/// The result of checking one part of a day
+#[derive(Debug, PartialEq, Eq)]
+pub struct Check {
+    pub day: u8,
+    pub part: u8,
+    pub outcome: Outcome,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Outcome {
+    Correct,
+    Wrong { expected: String, found: String },
+    /// There's no expected answer to compare against
+    Unknown,
+}
+
+impl fmt::Display for Check {
+    /// Describes the check, with a line-by-line diff if the answer was wrong
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "day {:02}, part {}: ", self.day, self.part)?;
+
+        match &self.outcome {
+            Outcome::Correct => write!(f, "correct"),
+            Outcome::Unknown => write!(f, "no expected answer"),
+            Outcome::Wrong { expected, found } => {
+                write!(f, "wrong answer")?;
+
+                let expected: Vec<&str> = expected.lines().collect();
+                let found: Vec<&str> = found.lines().collect();
+                for i in 0..expected.len().max(found.len()) {
+                    match (expected.get(i), found.get(i)) {
+                        (Some(e), Some(x)) if e == x => write!(f, "\n    {}", e)?,
+                        (e, x) => {
+                            if let Some(e) = e {
+                                write!(f, "\n  - {}", e)?;
+                            }
+                            if let Some(x) = x {
+                                write!(f, "\n  + {}", x)?;
+                            }
+                        }
+                    }
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
+// Parsing ---------------------------------------------------------------------
+
+/// Returns the line with any trailing comment removed
+fn _strip_comment(line: &str) -> &str {
+    // A '#' inside a string isn't a comment, and nor is an escaped '"' the end
+    // of one
+    let mut quoted = false;
+    let mut chars = line.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => quoted = !quoted,
+            '\\' if quoted => {
+                chars.next();
+            },
+            '#' if !quoted => return &line[..i],
+            _ => (),
+        }
+    }
+
+    line
+}
+
+/// Returns an integer value, which may be too large for any integer type
+fn _integer<'a>(input: &str, value: &'a str) -> Result<&'a str, ParseError> {
+    let digits = value.strip_prefix('-').unwrap_or(value);
+    match !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()) {
+        true => Ok(value),
+        false => Err(ParseError::at(input, value, "an integer or a string")),
+    }
+}
+
+/// Returns the contents of a `"..."` string, which must make up the whole value
+fn _basic_string(input: &str, value: &str) -> Result<String, ParseError> {
+    let body = &value[1..];
+    let mut contents = String::new();
+    let mut chars = body.char_indices();
+
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' if i + 1 == body.len() => return Ok(contents),
+            '"' => return Err(ParseError::at(input, &body[i + 1..], "the end of the line")),
+            '\\' => match chars.next() {
+                Some((_, 'n')) => contents.push('\n'),
+                Some((_, 't')) => contents.push('\t'),
+                Some((_, '"')) => contents.push('"'),
+                Some((_, '\\')) => contents.push('\\'),
+                Some((j, e)) => {
+                    let token = &body[j..(j + e.len_utf8())];
+                    return Err(ParseError::at(input, token, "an escape: `n`, `t`, `\"` or `\\`"));
+                },
+                None => break,
+            },
+            c => contents.push(c),
+        }
+    }
+
+    Err(ParseError::after(input, value, "a closing `\"`"))
+}
+
+/// Returns the contents of a `"""` string. `rest` is whatever follows the
+/// opening quotes on the same line, and `lines` holds the lines after it.
+fn _multiline_string<'a>(
+    input: &str,
+    rest: &str,
+    lines: &mut impl Iterator<Item = &'a str>,
+) -> Result<String, ParseError> {
+    if let Some(contents) = rest.strip_suffix("\"\"\"") {
+        return Ok(contents.to_string());
+    }
+
+    // As in TOML, a newline straight after the opening quotes is trimmed.
+    // Unlike TOML we also trim the one before the closing quotes, since no
+    // answer ends in a newline.
+    let mut contents: Vec<&str> = Vec::new();
+    if !rest.is_empty() {
+        contents.push(rest);
+    }
+
+    for line in lines {
+        if let Some(last) = line.strip_suffix("\"\"\"") {
+            if !last.is_empty() {
+                contents.push(last);
+            }
+            return Ok(contents.join("\n"));
+        }
+        contents.push(line);
+    }
+
+    Err(ParseError::eof(input, "a closing `\"\"\"`"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::bench::Stats;
+    use crate::solution::Answer;
+
+    #[test]
+    fn parse_answers() {
+        let answers = Answers::parse(r#"
+# Answers so far
+[day01]
+part1 = 67027 # a comment
+part2 = "19#7\"291" # the first `#` is in the string
+
+[ day11 ]
+part1 = -12
+part2 = 27142382301385558311211320
+
+[day10]
+part1 = """tab\t"""
+part2 = """
+#..#
+####
+"""
+"#).unwrap();
+
+        assert_eq!(answers.get(1, 1), Some("67027"));
+        assert_eq!(answers.get(1, 2), Some("19#7\"291"));
+        assert_eq!(answers.get(11, 1), Some("-12"));
+        assert_eq!(answers.get(11, 2), Some("27142382301385558311211320"));
+        assert_eq!(answers.get(10, 1), Some("tab\\t"));
+        assert_eq!(answers.get(10, 2), Some("#..#\n####"));
+        assert_eq!(answers.get(2, 1), None);
+        assert_eq!(_strip_comment(r#"part1 = "a\\" # b"#), r#"part1 = "a\\" "#);
+    }
+
+    #[test]
+    fn parse_errors() {
+        let errors = [
+            ("[day01", "`]`"),
+            ("[puzzle]", "a table named `dayNN`"),
+            ("[day26]", "a day from 1 to 25"),
+            ("part1 = 5", "a `[dayNN]` header first"),
+            ("[day01]\npart3 = 5", "`part1` or `part2`"),
+            ("[day01]\nanswer", "`[dayNN]` or `partN = answer`"),
+            ("[day01]\npart1 = 5x", "an integer or a string"),
+            ("[day01]\npart1 = -", "an integer or a string"),
+            ("[day01]\npart1 = \"a\\q\"", "an escape: `n`, `t`, `\"` or `\\`"),
+            ("[day01]\npart1 = \"a", "a closing `\"`"),
+            ("[day01]\npart1 = \"a\" b", "the end of the line"),
+            ("[day01]\npart1 = \"\"\"\na", "a closing `\"\"\"`"),
+            ("[day01]\npart1 = 1\npart1 = 2", "each part to be given only once"),
+        ];
+
+        for (input, expected) in errors {
+            assert_eq!(Answers::parse(input).unwrap_err().expected, expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn wrong_answers() {
+        let answers = Answers::parse("[day10]\npart1 = 13140\npart2 = \"\"\"\n#..#\n####\n##..\n\"\"\"").unwrap();
+        let stats = Stats::new(&mut [Duration::ZERO]);
+        let report = Report {
+            parse: stats,
+            answers: vec![
+                Answer { part: 1, value: "13140".to_string(), elapsed: stats },
+                Answer { part: 2, value: "#..#\n#..#".to_string(), elapsed: stats },
+            ],
+        };
+
+        let checks = answers.check(10, &report);
+        assert_eq!(checks[0].outcome, Outcome::Correct);
+        assert_eq!(checks[0].to_string(), "day 10, part 1: correct");
+        assert_eq!(checks[1].to_string(), "day 10, part 2: wrong answer\n    #..#\n  - ####\n  + #..#\n  - ##..");
+        assert_eq!(answers.check(11, &report)[0].outcome, Outcome::Unknown);
+    }
+}
@@ -0,0 +1,88 @@
+//! Repeated timings of the puzzle solutions
+//!
+//! Each step is timed on its own with `Instant`, so the figures don't include
+//! reading the input or dispatching to the right day. Warm-up runs give the
+//! caches and branch predictor a chance to settle before we start sampling.
+
+use std::time::{Duration, Instant};
+
+/// How many times to run each step of a solution
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bench {
+    /// Untimed runs before sampling begins
+    pub warmup: usize,
+    /// Timed runs
+    pub runs: usize,
+}
+
+impl Bench {
+    /// A single timed run, as used when we just want the answers
+    pub const ONCE: Bench = Bench { warmup: 0, runs: 1 };
+
+    /// Warm-up and timed runs used by `--bench` unless told otherwise
+    pub const DEFAULT: Bench = Bench { warmup: 3, runs: 10 };
+
+    /// Returns statistics for the time taken by `f`, along with the value it
+    /// returned on the final run
+    pub fn measure<T>(&self, mut f: impl FnMut() -> T) -> (T, Stats) {
+        for _ in 0..self.warmup {
+            std::hint::black_box(f());
+        }
+
+        let mut samples = Vec::with_capacity(self.runs);
+        let mut value = None;
+        for _ in 0..self.runs.max(1) {
+            let clock = Instant::now();
+            let x = std::hint::black_box(f());
+            samples.push(clock.elapsed());
+            value = Some(x);
+        }
+
+        (value.unwrap(), Stats::new(&mut samples))
+    }
+}
+
+/// Summary statistics for a set of timings
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stats {
+    pub min: Duration,
+    pub median: Duration,
+    pub mean: Duration,
+    pub stddev: Duration,
+}
+
+impl Stats {
+    /// Returns the statistics for some (non-empty) samples, sorting them
+    /// along the way
+    pub fn new(samples: &mut [Duration]) -> Self {
+        assert!(!samples.is_empty(), "no samples to summarise");
+        samples.sort();
+
+        let n = samples.len();
+        let median = match n % 2 {
+            0 => (samples[n / 2 - 1] + samples[n / 2]) / 2,
+            _ => samples[n / 2],
+        };
+
+        let mean = samples.iter().sum::<Duration>() / n as u32;
+
+        // Sample standard deviation, which is zero for a single run
+        let variance = match n {
+            1 => 0.0,
+            _ => {
+                samples
+                    .iter()
+                    .map(|s| (s.as_secs_f64() - mean.as_secs_f64()).powi(2))
+                    .sum::<f64>()
+                    / (n - 1) as f64
+            }
+        };
+
+        Stats {
+            min: samples[0],
+            median,
+            mean,
+            stddev: Duration::from_secs_f64(variance.sqrt()),
+        }
+    }
+}
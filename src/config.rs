@@ -1,25 +1,207 @@
 // Parse the CLI args
 
+use std::path::PathBuf;
+
+use crate::bench::Bench;
+use crate::output::Format;
+
+/// Where to read the puzzle input from
+#[derive(Debug, PartialEq, Eq)]
+pub enum Source {
+    /// `dayNN.txt` in the data directory
+    DataDir(PathBuf),
+    /// A file given explicitly on the command line
+    File(PathBuf),
+    Stdin,
+}
+
+/// Which days to run
+#[derive(Debug, PartialEq, Eq)]
+pub enum Selection {
+    Day(u8),
+    /// Every registered day, one after the other
+    All,
+}
+
+/// How to draw pictures of the solutions
+#[derive(Debug, PartialEq, Eq)]
+pub enum Render {
+    /// Print them with the answers
+    Text,
+    /// Write them to a PPM image at the given path
+    Image(PathBuf),
+}
+
+/// Percentage slow-down flagged by `--compare` unless told otherwise
+const DEFAULT_THRESHOLD: f64 = 10.0;
+
+/// What to do with the benchmark history
+#[derive(Debug, PartialEq)]
+pub struct History {
+    /// Directory holding the history file
+    pub dir: PathBuf,
+    /// Append this run's results to the history
+    pub save: bool,
+    /// Flag any step which is slower than in the history by more than this
+    /// percentage
+    pub threshold: Option<f64>,
+    /// Redraw the README's benchmark chart from the latest results
+    pub update_readme: bool,
+}
+
 pub struct Config {
-    pub day: u8,
+    pub selection: Selection,
     pub part: Option<u8>,
+    pub source: Source,
+    /// Set when the solutions should be timed over repeated runs
+    pub bench: Option<Bench>,
+    pub format: Format,
+    /// Set to the answers file when the answers should be checked
+    pub check: Option<PathBuf>,
+    pub history: History,
+    /// Set when pictures of the solutions should be drawn
+    pub render: Option<Render>,
+    /// Parameters for the day's solution, as names and values
+    pub params: Vec<(String, String)>,
 }
 
 impl Config {
-    pub fn new(mut args: impl Iterator<Item = String>) -> Result<Config, &'static str> {
+    pub fn new(mut args: impl Iterator<Item = String>) -> Result<Config, String> {
         args.next(); // First item is name of module
 
-        let day = match args.next() {
-            Some(arg) => parse_day(&arg)?,
-            None => return Err("not enough arguments"),
+        let mut positional = Vec::new();
+        let mut input = None;
+        let mut data_dir = PathBuf::from("data");
+        let mut all = false;
+        let mut bench = None;
+        let mut format = Format::Text;
+        let mut check = false;
+        let mut answers = None;
+        let mut render = None;
+        let mut params = Vec::new();
+        let mut history = History {
+            dir: PathBuf::from("bench"),
+            save: false,
+            threshold: None,
+            update_readme: false,
+        };
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--input" | "-i" => match args.next() {
+                    Some(path) => input = Some(path),
+                    None => return Err("--input requires a path, or '-' for stdin".into()),
+                },
+                "--data-dir" => match args.next() {
+                    Some(dir) => data_dir = PathBuf::from(dir),
+                    None => return Err("--data-dir requires a directory".into()),
+                },
+                "--all" => all = true,
+                "--check" => check = true,
+                "--save" => history.save = true,
+                "--update-readme" => history.update_readme = true,
+                "--compare" => {
+                    history.threshold.get_or_insert(DEFAULT_THRESHOLD);
+                },
+                "--threshold" => {
+                    let threshold = args
+                        .next()
+                        .and_then(|t| t.parse::<f64>().ok())
+                        .filter(|t| *t >= 0.0)
+                        .ok_or("--threshold requires a percentage")?;
+                    history.threshold = Some(threshold);
+                },
+                "--bench-dir" => match args.next() {
+                    Some(dir) => history.dir = PathBuf::from(dir),
+                    None => return Err("--bench-dir requires a directory".into()),
+                },
+                "--render" => render = Some(Render::Text),
+                "--render-to" => match args.next() {
+                    Some(path) => render = Some(Render::Image(PathBuf::from(path))),
+                    None => return Err("--render-to requires a path".into()),
+                },
+                "--param" => match args.next().as_deref().and_then(|p| p.split_once('=')) {
+                    Some((name, value)) => params.push((name.to_string(), value.to_string())),
+                    None => return Err("--param requires a name and value, such as rounds=100".into()),
+                },
+                "--answers" => match args.next() {
+                    Some(path) => answers = Some(PathBuf::from(path)),
+                    None => return Err("--answers requires a path".into()),
+                },
+                "--format" => {
+                    format = args
+                        .next()
+                        .and_then(|f| Format::from_name(&f))
+                        .ok_or("--format must be one of text, json or csv")?;
+                },
+                "--bench" => {
+                    bench.get_or_insert(Bench::DEFAULT);
+                },
+                "--runs" => {
+                    let runs = parse_count(args.next(), "--runs requires a positive integer")?;
+                    if runs == 0 {
+                        return Err("--runs requires a positive integer".into());
+                    }
+                    bench.get_or_insert(Bench::DEFAULT).runs = runs;
+                },
+                "--warmup" => {
+                    let warmup = parse_count(args.next(), "--warmup requires an integer")?;
+                    bench.get_or_insert(Bench::DEFAULT).warmup = warmup;
+                },
+                _ if arg.starts_with('-') && arg != "-" => return Err(format!("unknown option `{}`", arg)),
+                _ => positional.push(arg),
+            }
+        }
+
+        let mut positional = positional.iter().peekable();
+
+        if positional.peek().is_some_and(|arg| *arg == "all") {
+            positional.next();
+            all = true;
+        }
+
+        let selection = match positional.next() {
+            Some(_) if all => return Err("a day or part can't be chosen when running all days".into()),
+            Some(arg) => Selection::Day(parse_day(arg)?),
+            None if all => Selection::All,
+            None => return Err("not enough arguments".into()),
+        };
+
+        let part = match positional.next() {
+            Some(arg) => Some(parse_part(arg)?),
+            None => None,
         };
 
-        let part = match args.next() {
-            Some(arg) => Some(parse_part(&arg)?),
+        if positional.next().is_some() {
+            return Err("too many arguments".into());
+        }
+
+        if all && input.is_some() {
+            return Err("--input can't be used when running all days".into());
+        }
+
+        if all && render.is_some() {
+            return Err("solutions can only be drawn when running a single day".into());
+        }
+
+        if all && !params.is_empty() {
+            return Err("parameters can only be given when running a single day".into());
+        }
+
+        // Giving an answers file implies we want to check against it
+        let check = match answers {
+            Some(path) => Some(path),
+            None if check => Some(data_dir.join("answers.toml")),
             None => None,
         };
 
-        Ok(Config { day, part })
+        let source = match input.as_deref() {
+            Some("-") => Source::Stdin,
+            Some(path) => Source::File(PathBuf::from(path)),
+            None => Source::DataDir(data_dir),
+        };
+
+        Ok(Config { selection, part, source, bench, format, check, history, render, params })
     }
 }
 
@@ -51,3 +233,28 @@ fn parse_part(part: &str) -> Result<u8, &'static str> {
 
     Ok(part)
 }
+
+fn parse_count(count: Option<String>, err: &'static str) -> Result<usize, &'static str> {
+    count.and_then(|c| c.parse().ok()).ok_or(err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &str) -> Result<Config, String> {
+        Config::new(format!("aoc2022 {}", args).split_whitespace().map(String::from))
+    }
+
+    #[test]
+    fn options() {
+        let config = parse("5 2 -i - --param rounds=3").unwrap();
+        assert_eq!((config.selection, config.part, config.source), (Selection::Day(5), Some(2), Source::Stdin));
+        assert_eq!(config.params, [("rounds".to_string(), "3".to_string())]);
+
+        assert_eq!(parse("5 --foo").err().as_deref(), Some("unknown option `--foo`"));
+        assert_eq!(parse("-x 5").err().as_deref(), Some("unknown option `-x`"));
+        assert_eq!(parse("5 3").err().as_deref(), Some("part must be either 1 or 2"));
+        assert_eq!(parse("all --param rounds=3").err().as_deref(), Some("parameters can only be given when running a single day"));
+    }
+}
@@ -0,0 +1,305 @@
+//! An emulator for the handheld's CPU
+//!
+//! Each line of a program is decoded into an `Instruction`, which takes a
+//! number of cycles given by the CPU's `Costs` and only changes the registers
+//! once its last cycle is done. The CPU can be run a cycle or an instruction at
+//! a time, traced with a `Tick` for every cycle, or run until it reaches a
+//! `Breakpoint`: a given cycle, an instruction address, or a change in a
+//! watched register.
+//!
+//! New instructions need a variant of `Instruction`, a way of decoding it, a
+//! cost and an effect in `Cpu::_execute`. The rest of the emulator is the same
+//! for every instruction.
+
+use crate::error::ParseError;
+use crate::parse::integer;
+
+/// Registers are named by the lowercase letters
+pub type Register = char;
+
+const REGISTERS: usize = 26;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instruction {
+    Noop,
+    Add(Register, i32),
+}
+
+impl Instruction {
+    /// Decodes a line such as `noop` or `addx -5`, which must be a slice of
+    /// `input`
+    pub fn parse(input: &str, line: &str) -> Result<Self, ParseError> {
+        match line.split_once(' ') {
+            None if line == "noop" => Ok(Instruction::Noop),
+            Some((op, value)) if op.len() == 4 && op.starts_with("add") => {
+                let register = op.chars().last().unwrap();
+                if !register.is_ascii_lowercase() {
+                    return Err(ParseError::at(input, &op[3..], "a register from `a` to `z`"));
+                }
+                Ok(Instruction::Add(register, integer(input, value)?))
+            }
+            _ => Err(ParseError::at(input, line, "`noop` or `addx V`")),
+        }
+    }
+}
+
+/// The number of cycles each instruction takes, which must be at least one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Costs {
+    pub noop: usize,
+    pub add: usize,
+}
+
+impl Default for Costs {
+    fn default() -> Self {
+        Costs { noop: 1, add: 2 }
+    }
+}
+
+impl Costs {
+    fn of(&self, instruction: &Instruction) -> usize {
+        match instruction {
+            Instruction::Noop => self.noop,
+            Instruction::Add(..) => self.add,
+        }
+    }
+}
+
+/// Where the CPU should stop when running
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Breakpoint {
+    /// After the given cycle, counting from 1
+    Cycle(usize),
+    /// Before the instruction at the given address starts
+    Address(usize),
+    /// After a cycle which changes the register
+    Watch(Register),
+}
+
+/// Why the CPU stopped running
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stop {
+    Halted,
+    Break(Breakpoint),
+}
+
+/// The state of the CPU during a cycle
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tick {
+    /// The cycle, counting from 1
+    pub cycle: usize,
+    /// Address of the instruction being run
+    pub address: usize,
+    registers: [i32; REGISTERS],
+}
+
+impl Tick {
+    pub fn get(&self, register: Register) -> i32 {
+        self.registers[_index(register)]
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Cpu<'a> {
+    program: &'a [Instruction],
+    costs: Costs,
+    registers: [i32; REGISTERS],
+    address: usize,
+    cycle: usize,
+    busy: usize, // Cycles spent so far on the current instruction
+    breakpoints: Vec<Breakpoint>,
+    paused: bool, // Stopped by an address breakpoint, and not run since
+}
+
+impl<'a> Cpu<'a> {
+    /// Returns a CPU ready to run the program, with every register at zero
+    pub fn new(program: &'a [Instruction]) -> Self {
+        Cpu {
+            program,
+            costs: Costs::default(),
+            registers: [0; REGISTERS],
+            address: 0,
+            cycle: 0,
+            busy: 0,
+            breakpoints: Vec::new(),
+            paused: false,
+        }
+    }
+
+    /// Sets the number of cycles each instruction takes. Panics if any of them
+    /// take no cycles at all.
+    pub fn with_costs(mut self, costs: Costs) -> Self {
+        assert!(costs.noop > 0 && costs.add > 0, "instructions must take at least one cycle");
+        self.costs = costs;
+        self
+    }
+
+    pub fn with_register(mut self, register: Register, value: i32) -> Self {
+        self.registers[_index(register)] = value;
+        self
+    }
+
+    pub fn with_breakpoint(mut self, breakpoint: Breakpoint) -> Self {
+        self.breakpoints.push(breakpoint);
+        self
+    }
+
+    pub fn get(&self, register: Register) -> i32 {
+        self.registers[_index(register)]
+    }
+
+    /// Returns the number of cycles run so far
+    pub fn cycle(&self) -> usize {
+        self.cycle
+    }
+
+    pub fn halted(&self) -> bool {
+        self.address >= self.program.len()
+    }
+
+    /// Runs one cycle, returning the state during it, or `None` if the
+    /// program has finished
+    pub fn tick(&mut self) -> Option<Tick> {
+        let instruction = *self.program.get(self.address)?;
+        self.cycle += 1;
+        self.busy += 1;
+        self.paused = false;
+
+        let tick = Tick { cycle: self.cycle, address: self.address, registers: self.registers };
+
+        if self.busy >= self.costs.of(&instruction) {
+            self._execute(instruction);
+            self.address += 1;
+            self.busy = 0;
+        }
+
+        Some(tick)
+    }
+
+    /// Runs the rest of the current instruction, or the next one if between
+    /// instructions, and returns it
+    pub fn step(&mut self) -> Option<Instruction> {
+        let instruction = *self.program.get(self.address)?;
+        while self.tick().is_some() && self.busy > 0 {}
+
+        Some(instruction)
+    }
+
+    /// Runs until the program finishes or a breakpoint is reached. Address
+    /// breakpoints are checked before each instruction starts, so one at the
+    /// first instruction stops the CPU before it runs anything, and running
+    /// again carries on from it rather than stopping there twice.
+    pub fn run(&mut self) -> Stop {
+        loop {
+            if self.busy == 0 && !self.paused && !self.halted() {
+                let address = Breakpoint::Address(self.address);
+                if self.breakpoints.contains(&address) {
+                    self.paused = true;
+                    return Stop::Break(address);
+                }
+            }
+
+            let Some(tick) = self.tick() else {
+                return Stop::Halted;
+            };
+
+            let hit = self.breakpoints.iter().find(|&&b| match b {
+                Breakpoint::Cycle(cycle) => cycle == self.cycle,
+                Breakpoint::Address(_) => false,
+                Breakpoint::Watch(register) => tick.get(register) != self.get(register),
+            });
+
+            if let Some(&breakpoint) = hit {
+                return Stop::Break(breakpoint);
+            }
+        }
+    }
+
+    /// Returns an iterator which runs the program to the end, one cycle at a
+    /// time, ignoring breakpoints
+    pub fn trace(mut self) -> impl Iterator<Item = Tick> + 'a {
+        std::iter::from_fn(move || self.tick())
+    }
+
+    fn _execute(&mut self, instruction: Instruction) {
+        match instruction {
+            Instruction::Noop => (),
+            Instruction::Add(register, value) => self.registers[_index(register)] += value,
+        }
+    }
+}
+
+fn _index(register: Register) -> usize {
+    assert!(register.is_ascii_lowercase(), "`{}` isn't a register", register);
+    (register as u8 - b'a') as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn program(text: &str) -> Vec<Instruction> {
+        text.lines().map(|line| Instruction::parse(text, line).unwrap()).collect()
+    }
+
+    const PROGRAM: &str = "noop\naddx 3\naddy -5\nnoop";
+
+    #[test]
+    fn cpu_trace() {
+        let program = program(PROGRAM);
+        let trace = Cpu::new(&program).with_register('x', 1).trace().collect::<Vec<_>>();
+
+        // Registers only change once an instruction's last cycle is done
+        let during = trace.iter().map(|t| (t.cycle, t.address, t.get('x'), t.get('y'))).collect::<Vec<_>>();
+        assert_eq!(during, [(1, 0, 1, 0), (2, 1, 1, 0), (3, 1, 1, 0), (4, 2, 4, 0), (5, 2, 4, 0), (6, 3, 4, -5)]);
+    }
+
+    #[test]
+    fn cpu_costs() {
+        let program = program(PROGRAM);
+        let slow = Costs { noop: 2, add: 3 };
+        let trace = Cpu::new(&program).with_costs(slow).trace().collect::<Vec<_>>();
+        assert_eq!(trace.len(), 10);
+        assert_eq!((trace[4].address, trace[5].get('x')), (1, 3));
+
+        let mut cpu = Cpu::new(&program).with_costs(slow);
+        assert_eq!(cpu.step(), Some(Instruction::Noop));
+        assert_eq!(cpu.step(), Some(Instruction::Add('x', 3)));
+        assert_eq!(cpu.cycle(), 5);
+    }
+
+    #[test]
+    #[should_panic(expected = "instructions must take at least one cycle")]
+    fn cpu_free_instructions() {
+        let program = program(PROGRAM);
+        Cpu::new(&program).with_costs(Costs { noop: 0, add: 2 });
+    }
+
+    #[test]
+    fn cpu_breakpoints() {
+        let program = program(PROGRAM);
+        let mut cpu = Cpu::new(&program)
+            .with_register('x', 1)
+            .with_breakpoint(Breakpoint::Watch('x'))
+            .with_breakpoint(Breakpoint::Address(3));
+
+        assert_eq!(cpu.run(), Stop::Break(Breakpoint::Watch('x')));
+        assert_eq!((cpu.cycle(), cpu.get('x')), (3, 4));
+        assert_eq!(cpu.run(), Stop::Break(Breakpoint::Address(3)));
+        assert_eq!((cpu.cycle(), cpu.get('y')), (5, -5));
+        assert_eq!(cpu.step(), Some(Instruction::Noop));
+        assert!(cpu.halted());
+        assert_eq!(cpu.run(), Stop::Halted);
+
+        // The first instruction can be broken on, and running again carries on
+        let mut cpu = Cpu::new(&program)
+            .with_breakpoint(Breakpoint::Address(0))
+            .with_breakpoint(Breakpoint::Cycle(2));
+        assert_eq!(cpu.run(), Stop::Break(Breakpoint::Address(0)));
+        assert_eq!(cpu.cycle(), 0);
+        assert_eq!(cpu.run(), Stop::Break(Breakpoint::Cycle(2)));
+        assert_eq!((cpu.cycle(), cpu.get('x')), (2, 0));
+        assert_eq!(cpu.run(), Stop::Halted);
+        assert_eq!(cpu.cycle(), 6);
+    }
+}
@@ -1,17 +1,64 @@
-fn elf_totals() -> Vec<i32> {
-    include_str!("../../data/day01.txt")
-        .split("\r\n\r\n")
-        .map(|v| v.lines().map(|i| i.parse::<i32>().unwrap()).sum())
-        .collect()
-}
+use crate::error::ParseError;
+use crate::parse::{blocks, integer, normalise};
+use crate::solution::{Solution, Solved};
+
+pub struct Day01;
+
+impl Solution for Day01 {
+    type Input = Vec<i32>;
+    type Output1 = i32;
+    type Output2 = i32;
+
+    /// Returns the total calories carried by each elf
+    fn parse(input: &str) -> Result<Vec<i32>, ParseError> {
+        let input = &normalise(input);
+
+        blocks(input)
+            .map(|v| v.lines().map(|i| integer::<i32>(input, i)).sum())
+            .collect()
+    }
+
+    fn part1(totals: &Vec<i32>) -> Solved<i32> {
+        Ok(*totals.iter().max().unwrap())
+    }
+
+    fn part2(totals: &Vec<i32>) -> Solved<i32> {
+        let mut totals = totals.clone();
+        totals.sort();
 
-pub fn part1() -> i32 {
-    *elf_totals().iter().max().unwrap()
+        Ok(totals.iter().rev().take(3).sum())
+    }
 }
 
-pub fn part2() -> i32 {
-    let mut totals = elf_totals();
-    totals.sort();
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+1000
+2000
+3000
+
+4000
+
+5000
+6000
+
+7000
+8000
+9000
+
+10000";
+
+    #[test]
+    fn part1_example() {
+        let input = Day01::parse(EXAMPLE).unwrap();
+        assert_eq!(Day01::part1(&input).unwrap(), 24000);
+    }
 
-    totals.iter().rev().take(3).sum()
+    #[test]
+    fn part2_example() {
+        let input = Day01::parse(EXAMPLE).unwrap();
+        assert_eq!(Day01::part2(&input).unwrap(), 45000);
+    }
 }
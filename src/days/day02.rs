@@ -1,31 +1,24 @@
+use crate::error::ParseError;
+use crate::parse::normalise;
+use crate::solution::{Solution, Solved};
+
 #[derive(PartialEq, Eq, Clone)]
-enum Shape {
+pub enum Shape {
     Rock,
     Paper,
     Scissors,
 }
 
 /// Convert character to shape
-fn parse_shape(x: &u8) -> Result<Shape, String> {
+fn parse_shape(x: &str) -> Option<Shape> {
     match x {
-        b'A' | b'X' => Ok(Shape::Rock),
-        b'B' | b'Y' => Ok(Shape::Paper),
-        b'C' | b'Z' => Ok(Shape::Scissors),
-        _ => Err("Unrecognised shape".to_string()),
+        "A" | "X" => Some(Shape::Rock),
+        "B" | "Y" => Some(Shape::Paper),
+        "C" | "Z" => Some(Shape::Scissors),
+        _ => None,
     }
 }
 
-/// Read shapes from input, return a vector of tuples containing the shapes
-/// used in each rock-paper-scissors game.
-///
-/// Panics if input is not in expected format
-fn read_shapes() -> Vec<(Shape, Shape)> {
-    return include_bytes!("../../data/day02.txt")
-        .split(|b| *b == b'\n')
-        .map(|l| (parse_shape(&l[0]).unwrap(), parse_shape(&l[2]).unwrap()))
-        .collect();
-}
-
 /// Return the score for a given shape
 fn score_shape(shape: &Shape) -> u8 {
     match shape {
@@ -66,24 +59,74 @@ fn swap_shape(me: &Shape, elf: &Shape) -> Shape {
     }
 }
 
-pub fn part1() -> u32 {
-    let mut solution: u32 = 0;
+pub struct Day02;
+
+impl Solution for Day02 {
+    type Input = Vec<(Shape, Shape)>;
+    type Output1 = u32;
+    type Output2 = u32;
 
-    for (elf, me) in read_shapes() {
-        solution += (score_shape(&me) + score_match(&me, &elf)) as u32
+    /// Read shapes from input, return a vector of tuples containing the shapes
+    /// used in each rock-paper-scissors game.
+    fn parse(input: &str) -> Result<Vec<(Shape, Shape)>, ParseError> {
+        let input = &normalise(input);
+
+        input
+            .lines()
+            .map(|l| {
+                let (elf, me) = l
+                    .split_once(' ')
+                    .ok_or_else(|| ParseError::after(input, l, "a space between shapes"))?;
+
+                let elf = parse_shape(elf).ok_or_else(|| ParseError::at(input, elf, "A, B or C"))?;
+                let me = parse_shape(me).ok_or_else(|| ParseError::at(input, me, "X, Y or Z"))?;
+
+                Ok((elf, me))
+            })
+            .collect()
     }
 
-    solution
+    fn part1(games: &Vec<(Shape, Shape)>) -> Solved<u32> {
+        let mut solution: u32 = 0;
+
+        for (elf, me) in games {
+            solution += (score_shape(me) + score_match(me, elf)) as u32
+        }
+
+        Ok(solution)
+    }
+
+    fn part2(games: &Vec<(Shape, Shape)>) -> Solved<u32> {
+        let mut solution: u32 = 0;
+        let mut my_shape: Shape;
+
+        for (elf, me) in games {
+            my_shape = swap_shape(me, elf);
+            solution += (score_shape(&my_shape) + score_match(&my_shape, elf)) as u32
+        }
+
+        Ok(solution)
+    }
 }
 
-pub fn part2() -> u32 {
-    let mut solution: u32 = 0;
-    let mut my_shape: Shape;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    for (elf, me) in read_shapes() {
-        my_shape = swap_shape(&me, &elf);
-        solution += (score_shape(&my_shape) + score_match(&my_shape, &elf)) as u32
+    const EXAMPLE: &str = "\
+A Y
+B X
+C Z";
+
+    #[test]
+    fn part1_example() {
+        let input = Day02::parse(EXAMPLE).unwrap();
+        assert_eq!(Day02::part1(&input).unwrap(), 15);
     }
 
-    solution
+    #[test]
+    fn part2_example() {
+        let input = Day02::parse(EXAMPLE).unwrap();
+        assert_eq!(Day02::part2(&input).unwrap(), 12);
+    }
 }
@@ -1,5 +1,9 @@
 use std::collections::{hash_map::RandomState, HashSet};
 
+use crate::error::ParseError;
+use crate::parse::normalise;
+use crate::solution::{Solution, Solved};
+
 /// Returns a tuple of sets representing the compartments
 fn compartments(x: &[u8]) -> (HashSet<u8>, HashSet<u8>) {
     let (left, right) = x.split_at(x.len() / 2);
@@ -38,24 +42,84 @@ fn badge_priority(x: &[u8], y: &[u8], z: &[u8]) -> i32 {
     priority(*badge)
 }
 
-/// Sum of bag priorities
-pub fn part1() -> i32 {
-    include_bytes!("../../data/day03.txt")
-        .split(|b| *b == b'\n')
-        .map(compartments)
-        .map(|(x, y)| bag_priority(x, y))
-        .sum()
+pub struct Day03;
+
+impl Solution for Day03 {
+    type Input = Vec<Vec<u8>>;
+    type Output1 = i32;
+    type Output2 = i32;
+
+    /// Returns the contents of each backpack
+    fn parse(input: &str) -> Result<Vec<Vec<u8>>, ParseError> {
+        let input = &normalise(input);
+
+        let bags: Vec<Vec<u8>> = input
+            .lines()
+            .map(|l| match l.find(|c: char| !c.is_ascii_alphabetic()) {
+                Some(i) => Err(ParseError::at(input, &l[i..i + 1], "an item from a-z or A-Z")),
+                None => Ok(l.as_bytes().to_vec()),
+            })
+            .collect::<Result<_, _>>()?;
+
+        // Part two takes the elves in groups of three
+        if !bags.len().is_multiple_of(3) {
+            return Err(ParseError::eof(input, "another backpack, to make a group of three"));
+        }
+
+        Ok(bags)
+    }
+
+    /// Sum of bag priorities
+    fn part1(bags: &Vec<Vec<u8>>) -> Solved<i32> {
+        let priorities = bags
+            .iter()
+            .map(|b| compartments(b))
+            .map(|(x, y)| bag_priority(x, y));
+
+        Ok(priorities.sum())
+    }
+
+    /// Sum of badge priorities
+    fn part2(bags: &Vec<Vec<u8>>) -> Solved<i32> {
+        let mut elves = bags.iter();
+
+        let mut solution = 0;
+
+        while let Some(e1) = elves.next() {
+            solution += badge_priority(e1, elves.next().unwrap(), elves.next().unwrap())
+        }
+
+        Ok(solution)
+    }
 }
 
-/// Sum of badge priorities
-pub fn part2() -> i32 {
-    let mut elves = include_bytes!("../../data/day03.txt").split(|b| *b == b'\n');
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+vJrwpWtwJgWrhcsFMMfFFhFp
+jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL
+PmmdzqPrVvPwwTWBwg
+wMqvLMZHhHMvwLHjbvcjnnSBnvTQFn
+ttgJtRGJQctTZtZT
+CrZsJsPPZsGzwwsLwLmpwMDw";
 
-    let mut solution = 0;
+    #[test]
+    fn part1_example() {
+        let input = Day03::parse(EXAMPLE).unwrap();
+        assert_eq!(Day03::part1(&input).unwrap(), 157);
+    }
 
-    while let Some(e1) = elves.next() {
-        solution += badge_priority(e1, elves.next().unwrap(), elves.next().unwrap())
+    #[test]
+    fn part2_example() {
+        let input = Day03::parse(EXAMPLE).unwrap();
+        assert_eq!(Day03::part2(&input).unwrap(), 70);
     }
 
-    solution
+    #[test]
+    fn incomplete_group() {
+        let error = Day03::parse(EXAMPLE.rsplit_once('\n').unwrap().0).unwrap_err();
+        assert_eq!(error.expected, "another backpack, to make a group of three");
+    }
 }
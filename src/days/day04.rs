@@ -1,17 +1,23 @@
-// TODO: Would be nice not to have to do the iter->vec->iter round trip.
-fn parse() -> Vec<[u8; 4]> {
-    include_str!("../../data/day04.txt")
-        .lines()
-        .map(parse_line)
-        .collect()
-}
+use crate::error::ParseError;
+use crate::parse::{integer, normalise};
+use crate::solution::{Solution, Solved};
+
+fn parse_line(input: &str, x: &str) -> Result<[u8; 4], ParseError> {
+    let (left, right) = x
+        .split_once(',')
+        .ok_or_else(|| ParseError::after(input, x, "a comma between pairs"))?;
+
+    let mut bounds = [0; 4];
+    for (i, range) in [left, right].into_iter().enumerate() {
+        let (lower, upper) = range
+            .split_once('-')
+            .ok_or_else(|| ParseError::after(input, range, "a range such as 2-4"))?;
 
-fn parse_line(x: &str) -> [u8; 4] {
-    x.split_terminator(['-', ','])
-        .map(|i| i.parse::<u8>().unwrap())
-        .collect::<Vec<u8>>()[..4]
-        .try_into()
-        .unwrap()
+        bounds[2 * i] = integer(input, lower)?;
+        bounds[2 * i + 1] = integer(input, upper.trim_end())?;
+    }
+
+    Ok(bounds)
 }
 
 /// Returns true if either pair is fully contained by the other, else false
@@ -26,11 +32,50 @@ fn partial_overlap([l1, u1, l2, u2]: &[u8; 4]) -> bool {
     ((l2 <= l1) & (u2 >= l1)) // [ ( ] )
 }
 
-/// Count the number of 'contained' pairs
-pub fn part1() -> usize {
-    parse().iter().filter(|&v| full_overlap(v)).count()
+pub struct Day04;
+
+impl Solution for Day04 {
+    type Input = Vec<[u8; 4]>;
+    type Output1 = usize;
+    type Output2 = usize;
+
+    fn parse(input: &str) -> Result<Vec<[u8; 4]>, ParseError> {
+        let input = &normalise(input);
+
+        input.lines().map(|l| parse_line(input, l)).collect()
+    }
+
+    /// Count the number of 'contained' pairs
+    fn part1(pairs: &Vec<[u8; 4]>) -> Solved<usize> {
+        Ok(pairs.iter().filter(|&v| full_overlap(v)).count())
+    }
+
+    fn part2(pairs: &Vec<[u8; 4]>) -> Solved<usize> {
+        Ok(pairs.iter().filter(|&v| partial_overlap(v)).count())
+    }
 }
 
-pub fn part2() -> usize {
-    parse().iter().filter(|&v| partial_overlap(v)).count()
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+2-4,6-8
+2-3,4-5
+5-7,7-9
+2-8,3-7
+6-6,4-6
+2-6,4-8";
+
+    #[test]
+    fn part1_example() {
+        let input = Day04::parse(EXAMPLE).unwrap();
+        assert_eq!(Day04::part1(&input).unwrap(), 2);
+    }
+
+    #[test]
+    fn part2_example() {
+        let input = Day04::parse(EXAMPLE).unwrap();
+        assert_eq!(Day04::part2(&input).unwrap(), 4);
+    }
 }
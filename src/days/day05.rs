@@ -1,79 +1,143 @@
-use std::{fs::read_to_string, vec};
+use std::vec;
+
+use crate::error::ParseError;
+use crate::parse::{ints, normalise, Pattern};
+use crate::solution::{Solution, Solved};
 
 type Instruction = (usize, usize, usize);
 type Stack = Vec<char>;
 
 // Parsing ---------------------------------------------------------------------
 
-fn parse_stacks(x: &str) -> Vec<Stack> {
-    let layers: Vec<Vec<char>> = x.lines().map(|l| l.chars().collect()).rev().collect();
-
-    let nstacks = layers[0].len() / 4;
+fn parse_stacks(input: &str, x: &str) -> Result<Vec<Stack>, ParseError> {
+    let layers: Vec<&str> = x.lines().rev().collect();
+
+    let indices = layers
+        .first()
+        .ok_or_else(|| ParseError::at(input, x, "a drawing of the stacks"))?;
+    // Each crate sits directly above its stack's index
+    let columns = ints::<usize>(input, indices)
+        .with_offsets()
+        .map(|index| index.map(|(offset, _)| offset))
+        .collect::<Result<Vec<usize>, ParseError>>()?;
+    let nstacks = columns.len();
     let mut stacks: Vec<Stack> = vec![Vec::new(); nstacks];
 
     // First layer is stack indices, so skip it
     for layer in layers.iter().skip(1) {
         // First character is '[', so skip it
         // Thereafter, stacks are separated by '] ['
-        for (i, c) in layer.iter().skip(1).step_by(4).enumerate() {
-            if !c.is_whitespace() {
-                stacks[i].push(*c);
+        for (pos, c) in layer.char_indices().skip(1).step_by(4) {
+            if c.is_whitespace() {
+                continue;
+            }
+            match columns.iter().position(|&col| col == pos) {
+                Some(i) => stacks[i].push(c),
+                None => {
+                    let token = &layer[pos..(pos + c.len_utf8())];
+                    return Err(ParseError::at(input, token, format!("a crate above one of the {} stacks", nstacks)));
+                }
             }
         }
     }
 
-    stacks
+    Ok(stacks)
 }
 
-fn parse_instructions(x: &str) -> Vec<Instruction> {
-    x.lines().map(_parse_instruction).collect()
+const INSTRUCTION: &str = "move {n} from {from} to {to}";
+
+fn parse_instructions(input: &str, x: &str, nstacks: usize) -> Result<Vec<Instruction>, ParseError> {
+    let pattern = Pattern::new(INSTRUCTION);
+    x.lines().map(|l| _parse_instruction(input, &pattern, l, nstacks)).collect()
 }
 
-fn _parse_instruction(x: &str) -> Instruction {
-    let x = x[5..] // Instructions begin with 'move '
-        .replace("from", "")
-        .replace("to", "");
+/// Parses an instruction of the form 'move N from A to B' with the
+/// `INSTRUCTION` pattern
+fn _parse_instruction(input: &str, pattern: &Pattern, x: &str, nstacks: usize) -> Result<Instruction, ParseError> {
+    let fields = pattern.parse(input, x)?;
 
-    let mut m = x.split_whitespace().map(|v| v.parse::<usize>().unwrap());
+    let stack = |name: &str| -> Result<usize, ParseError> {
+        match fields.get(name)? {
+            i if (1..=nstacks).contains(&i) => Ok(i - 1),
+            _ => Err(ParseError::at(input, fields.slice(name), format!("a stack from 1 to {}", nstacks))),
+        }
+    };
 
-    (
-        m.next().unwrap(),
-        m.next().unwrap() - 1,
-        m.next().unwrap() - 1,
-    )
+    Ok((fields.get("n")?, stack("from")?, stack("to")?))
 }
 
-fn parse() -> (Vec<Stack>, Vec<Instruction>) {
-    let input = read_to_string("data/day05.txt").unwrap();
-    let (stacks, instructions) = input.split_once("\n\r\n").unwrap();
+// Logic -----------------------------------------------------------------------
 
-    (parse_stacks(stacks), parse_instructions(instructions))
-}
+pub struct Day05;
 
-// Logic -----------------------------------------------------------------------
+impl Solution for Day05 {
+    type Input = (Vec<Stack>, Vec<Instruction>);
+    type Output1 = String;
+    type Output2 = String;
+
+    fn parse(input: &str) -> Result<(Vec<Stack>, Vec<Instruction>), ParseError> {
+        let input = &normalise(input);
+
+        let (stacks, instructions) = input
+            .split_once("\n\n")
+            .ok_or_else(|| ParseError::eof(input, "a blank line after the stacks"))?;
+
+        let stacks = parse_stacks(input, stacks)?;
+        let instructions = parse_instructions(input, instructions, stacks.len())?;
 
-pub fn part1() -> String {
-    let (mut stacks, instructions) = parse();
+        Ok((stacks, instructions))
+    }
+
+    fn part1((stacks, instructions): &(Vec<Stack>, Vec<Instruction>)) -> Solved<String> {
+        let mut stacks = stacks.clone();
 
-    for (n, from, to) in instructions {
-        for _ in 0..n {
-            let v = stacks[from].pop().unwrap();
-            stacks[to].push(v);
+        for &(n, from, to) in instructions {
+            for _ in 0..n {
+                let v = stacks[from].pop().unwrap();
+                stacks[to].push(v);
+            }
         }
+
+        Ok(stacks.iter().map(|s| s.last().unwrap()).collect())
     }
 
-    stacks.iter().map(|s| s.last().unwrap()).collect()
+    fn part2((stacks, instructions): &(Vec<Stack>, Vec<Instruction>)) -> Solved<String> {
+        let mut stacks = stacks.clone();
+        let mut collected: Stack = Vec::new(); // To satisfy the borrow checker
+
+        for &(n, from, to) in instructions {
+            let left = stacks[from].len() - n;
+            collected.extend(stacks[from].drain(left..));
+            stacks[to].append(&mut collected);
+        }
+
+        Ok(stacks.iter().map(|s| s.last().unwrap()).collect())
+    }
 }
 
-pub fn part2() -> String {
-    let (mut stacks, instructions) = parse();
-    let mut collected: Stack = Vec::new(); // To satisfy the borrow checker
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "    [D]    
+[N] [C]    
+[Z] [M] [P]
+ 1   2   3 
 
-    for (n, from, to) in instructions {
-        let left = stacks[from].len() - n;
-        collected.extend(stacks[from].drain(left..));
-        stacks[to].append(&mut collected);
+move 1 from 2 to 1
+move 3 from 1 to 3
+move 2 from 2 to 1
+move 1 from 1 to 2";
+
+    #[test]
+    fn part1_example() {
+        let input = Day05::parse(EXAMPLE).unwrap();
+        assert_eq!(Day05::part1(&input).unwrap(), "CMZ");
     }
 
-    stacks.iter().map(|s| s.last().unwrap()).collect()
+    #[test]
+    fn part2_example() {
+        let input = Day05::parse(EXAMPLE).unwrap();
+        assert_eq!(Day05::part2(&input).unwrap(), "MCD");
+    }
 }
@@ -1,7 +1,10 @@
+use crate::error::ParseError;
+use crate::parse::normalise;
+use crate::solution::{Solution, Solved};
+
 /// Return the index of the first character which is preceeded by `size` unique
-/// characters (inclusive of final character).
-fn locate_marker(size: usize) -> usize {
-    let buffer = include_bytes!("../../data/day06.txt");
+/// characters (inclusive of final character), if there is one.
+fn locate_marker(buffer: &[u8], size: usize) -> Option<usize> {
     let mut window: Vec<u8> = Vec::new();
 
     for (i, x) in buffer.iter().enumerate() {
@@ -11,17 +14,59 @@ fn locate_marker(size: usize) -> usize {
         window.push(*x);
 
         if window.len() == size {
-            return i + 1;
+            return Some(i + 1);
         }
     }
 
-    unreachable!()
+    None
 }
 
-pub fn part1() -> usize {
-    locate_marker(4)
+pub struct Day06;
+
+impl Solution for Day06 {
+    type Input = Vec<u8>;
+    type Output1 = usize;
+    type Output2 = usize;
+
+    fn parse(input: &str) -> Result<Vec<u8>, ParseError> {
+        let input = &normalise(input);
+
+        Ok(input.as_bytes().to_vec())
+    }
+
+    fn part1(buffer: &Vec<u8>) -> Solved<usize> {
+        Ok(locate_marker(buffer, 4).ok_or("no start-of-packet marker")?)
+    }
+
+    fn part2(buffer: &Vec<u8>) -> Solved<usize> {
+        Ok(locate_marker(buffer, 14).ok_or("no start-of-message marker")?)
+    }
 }
 
-pub fn part2() -> usize {
-    locate_marker(14)
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+mjqjpqmgbljsphdztnvjfqwrcgsmlb";
+
+    #[test]
+    fn part1_example() {
+        let input = Day06::parse(EXAMPLE).unwrap();
+        assert_eq!(Day06::part1(&input).unwrap(), 7);
+    }
+
+    #[test]
+    fn part2_example() {
+        let input = Day06::parse(EXAMPLE).unwrap();
+        assert_eq!(Day06::part2(&input).unwrap(), 19);
+    }
+
+    #[test]
+    fn no_marker() {
+        let input = Day06::parse("abcdabcdabcdabcdabcd").unwrap();
+        assert_eq!(Day06::part1(&input).unwrap(), 4);
+        assert_eq!(Day06::part2(&input).unwrap_err().to_string(), "no start-of-message marker");
+        assert!(Day06::part1(&Day06::parse("").unwrap()).is_err());
+    }
 }
@@ -1,29 +1,8 @@
-use std::{collections::BTreeMap, fs::read_to_string};
+use std::collections::BTreeMap;
 
-/// Returns the sizes of each directory in the tree
-fn parse() -> BTreeMap<String, u32> {
-    let input = read_to_string("data/day07.txt").unwrap();
-
-    let mut directory_sizes: BTreeMap<String, u32> = BTreeMap::new();
-    let mut path: Vec<String> = Vec::new();
-
-    for line in input.lines() {
-        let x = line.replace("$ ", "");
-        let mut couplet = x.split_whitespace();
-        let prefix = couplet.next().unwrap();
-
-        match prefix {
-            "cd" => change_directory(&mut path, couplet.next().unwrap()),
-            "ls" => (),
-            x if x.starts_with('d') => (),
-
-            // If prefix isn't matched yet, then it's a filesize!
-            _ => add_filesize(&mut directory_sizes, &path, prefix),
-        }
-    }
-
-    directory_sizes
-}
+use crate::error::ParseError;
+use crate::parse::{integer, normalise};
+use crate::solution::{Solution, Solved};
 
 /// Updates path according to the command
 fn change_directory(path: &mut Vec<String>, commands: &str) {
@@ -44,8 +23,7 @@ fn change_directory(path: &mut Vec<String>, commands: &str) {
 }
 
 /// Updates sizes by adding the file size to each directory in the path
-fn add_filesize(sizes: &mut BTreeMap<String, u32>, path: &Vec<String>, file: &str) {
-    let size = file.parse::<u32>().unwrap();
+fn add_filesize(sizes: &mut BTreeMap<String, u32>, path: &Vec<String>, size: u32) {
     let mut d = String::from("");
 
     for p in path {
@@ -59,18 +37,108 @@ fn add_filesize(sizes: &mut BTreeMap<String, u32>, path: &Vec<String>, file: &st
     }
 }
 
-/// Returns the sum of the sizes of all directories with sizes < 100,000
-pub fn part1() -> u32 {
-    let sizes = parse();
+pub struct Day07;
+
+impl Solution for Day07 {
+    type Input = BTreeMap<String, u32>;
+    type Output1 = u32;
+    type Output2 = u32;
+
+    /// Returns the sizes of each directory in the tree
+    fn parse(input: &str) -> Result<BTreeMap<String, u32>, ParseError> {
+        let input = &normalise(input);
+
+        let mut directory_sizes: BTreeMap<String, u32> = BTreeMap::new();
+        let mut path: Vec<String> = Vec::new();
+
+        for line in input.lines() {
+            let x = line.strip_prefix("$ ").unwrap_or(line);
+            let mut couplet = x.split_whitespace();
+            let prefix = couplet
+                .next()
+                .ok_or_else(|| ParseError::at(input, line, "a command or a listing"))?;
+
+            match prefix {
+                "cd" => {
+                    let dir = couplet
+                        .next()
+                        .ok_or_else(|| ParseError::after(input, line, "a directory"))?;
+                    change_directory(&mut path, dir)
+                }
+                "ls" => (),
+                x if x.starts_with('d') => (),
+
+                // If prefix isn't matched yet, then it's a filesize!
+                _ => add_filesize(&mut directory_sizes, &path, integer(input, prefix)?),
+            }
+        }
+
+        Ok(directory_sizes)
+    }
+
+    /// Returns the sum of the sizes of all directories with sizes < 100,000
+    fn part1(sizes: &BTreeMap<String, u32>) -> Solved<u32> {
+        Ok(sizes.values().filter(|&s| *s < 100_000).sum())
+    }
+
+    /// Returns the size of the smallest directory we need to delete to
+    /// reduce occupied disk space below 40,000,000
+    fn part2(sizes: &BTreeMap<String, u32>) -> Solved<u32> {
+        let used = *sizes.get("./").ok_or("no files under `/`")?;
+        let delete_size = used
+            .checked_sub(40_000_000)
+            .ok_or("there's already enough free space")?;
 
-    sizes.values().filter(|&s| *s < 100_000).sum()
+        // The root directory is always big enough, so there's a minimum
+        Ok(*sizes.values().filter(|&s| *s > delete_size).min().unwrap())
+    }
 }
 
-/// Returns the size of the smallest directory we need to delete to
-/// reduce occupied disk space below 40,000,000
-pub fn part2() -> u32 {
-    let sizes = parse();
-    let delete_size = sizes.get("./").unwrap() - 40_000_000;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+$ cd /
+$ ls
+dir a
+14848514 b.txt
+8504156 c.dat
+dir d
+$ cd a
+$ ls
+dir e
+29116 f
+2557 g
+62596 h.lst
+$ cd e
+$ ls
+584 i
+$ cd ..
+$ cd ..
+$ cd d
+$ ls
+4060174 j
+8033020 d.log
+5626152 d.ext
+7214296 k";
+
+    #[test]
+    fn part1_example() {
+        let input = Day07::parse(EXAMPLE).unwrap();
+        assert_eq!(Day07::part1(&input).unwrap(), 95437);
+    }
 
-    *sizes.values().filter(|&s| *s > delete_size).min().unwrap()
+    #[test]
+    fn part2_example() {
+        let input = Day07::parse(EXAMPLE).unwrap();
+        assert_eq!(Day07::part2(&input).unwrap(), 24933642);
+    }
+
+    #[test]
+    fn unsolvable() {
+        let input = Day07::parse("$ cd /\n$ ls\n100 a.txt").unwrap();
+        assert_eq!(Day07::part2(&input).unwrap_err().to_string(), "there's already enough free space");
+        assert!(Day07::part2(&Day07::parse("$ ls").unwrap()).is_err());
+    }
 }
@@ -1,66 +1,90 @@
-const WIDTH: usize = 99;
-
-/// Returns a 99x99 grid stored as a 9801 length vector
-fn parse() -> Vec<u8> {
-    include_bytes!("../../data/day08.txt")
-        .chunks(WIDTH + 2) // row plus '\n\r'
-        .flat_map(|l| l.strip_suffix(b"\r\n").unwrap_or(l))
-        .copied()
-        .collect()
+use crate::error::ParseError;
+use crate::grid::{Grid, ORTHOGONAL};
+use crate::parse::normalise;
+use crate::solution::{Solution, Solved};
+
+pub struct Day08;
+
+impl Solution for Day08 {
+    type Input = Grid<u8>;
+    type Output1 = usize;
+    type Output2 = u32;
+
+    /// Returns the grid of tree heights
+    fn parse(input: &str) -> Result<Grid<u8>, ParseError> {
+        let input = &normalise(input);
+
+        if let Some(i) = input.find(|c: char| !(c.is_ascii_digit() || c == '\n')) {
+            return Err(ParseError::at_byte(input, i, "a tree height from 0 to 9"));
+        }
+
+        Grid::parse(input)
+    }
+
+    /// Count the number of trees visible from the outside
+    fn part1(x: &Grid<u8>) -> Solved<usize> {
+        Ok(visible(x))
+    }
+
+    /// Returns the maximum 'scenic score' from among all the trees
+    fn part2(x: &Grid<u8>) -> Solved<u32> {
+        Ok(max_scenic_score(x))
+    }
 }
 
 /// Count the number of trees visible from the outside
-pub fn part1() -> usize {
-    let x = parse();
+fn visible(x: &Grid<u8>) -> usize {
+    let (nrow, ncol) = (x.nrow, x.ncol);
+    let mut visibility = Grid::new(vec![false; x.len()], nrow);
 
-    let mut visibility = vec![false; x.len()];
-    let mut pos: usize;
-    for i in 0..WIDTH {
-        let [mut hl, mut hr, mut ht, mut hb] = [0; 4];
+    // Looking along each row
+    for i in 0..nrow {
+        let [mut hl, mut hr] = [0; 2];
 
-        for j in 0..WIDTH {
+        for j in 0..ncol {
             // Moving L-R
-            pos = i * WIDTH + j;
-            if x[pos] > hl {
-                hl = x[pos];
-                visibility[pos] = true;
+            if x.get(i, j) > hl {
+                hl = x.get(i, j);
+                visibility.set(i, j, true);
             }
 
             // Moving R-L
-            pos = i * WIDTH + WIDTH - j - 1;
-            if x[pos] > hr {
-                hr = x[pos];
-                visibility[pos] = true;
+            if x.get(i, ncol - j - 1) > hr {
+                hr = x.get(i, ncol - j - 1);
+                visibility.set(i, ncol - j - 1, true);
             }
+        }
+    }
 
+    // Looking along each column
+    for j in 0..ncol {
+        let [mut ht, mut hb] = [0; 2];
+
+        for i in 0..nrow {
             // Moving T-B
-            pos = j * WIDTH + i;
-            if x[pos] > ht {
-                ht = x[pos];
-                visibility[pos] = true;
+            if x.get(i, j) > ht {
+                ht = x.get(i, j);
+                visibility.set(i, j, true);
             }
 
             // Moving B-T
-            pos = (WIDTH - j - 1) * WIDTH + i;
-            if x[pos] > hb {
-                hb = x[pos];
-                visibility[pos] = true;
+            if x.get(nrow - i - 1, j) > hb {
+                hb = x.get(nrow - i - 1, j);
+                visibility.set(nrow - i - 1, j, true);
             }
         }
     }
 
-    visibility.iter().filter(|b| **b).count()
+    visibility.iter().filter(|&(_, visible)| visible).count()
 }
 
 /// Returns the maximum 'scenic score' from among all the trees
-pub fn part2() -> u32 {
-    let x = parse();
-
+fn max_scenic_score(x: &Grid<u8>) -> u32 {
     let mut max_score = 0;
-    for i in 1..(WIDTH - 1) {
+    for i in 1..(x.nrow.saturating_sub(1)) {
         // Don't need to consider exterior trees
-        for j in 1..(WIDTH - 1) {
-            let score = scenic_score(i, j, &x);
+        for j in 1..(x.ncol.saturating_sub(1)) {
+            let score = scenic_score(i, j, x);
             if score > max_score {
                 max_score = score;
             };
@@ -70,42 +94,46 @@ pub fn part2() -> u32 {
     max_score
 }
 
-/// Returns the scenic score at a given position
-fn scenic_score(i: usize, j: usize, x: &[u8]) -> u32 {
-    let h = x[i * WIDTH + j];
-    let [mut l, mut r, mut t, mut b] = [0; 4];
-
-    // Moving L-R
-    for k in 1..(WIDTH - j) {
-        l += 1;
-        if h <= x[i * WIDTH + j + k] {
-            break;
-        }
-    }
-
-    // Moving R-L
-    for k in 1..(j + 1) {
-        r += 1;
-        if h <= x[i * WIDTH + j - k] {
-            break;
-        }
-    }
+/// Returns the scenic score at a given position: the product of the number of
+/// trees which can be seen looking in each direction
+fn scenic_score(i: usize, j: usize, x: &Grid<u8>) -> u32 {
+    let h = x.get(i, j);
+
+    ORTHOGONAL
+        .iter()
+        .map(|&direction| {
+            let mut seen = 0;
+            for (ri, rj) in x.ray(i, j, direction) {
+                seen += 1;
+                if h <= x.get(ri, rj) {
+                    break;
+                }
+            }
+            seen
+        })
+        .product()
+}
 
-    // Moving T-B
-    for k in 1..(WIDTH - i) {
-        t += 1;
-        if h <= x[(i + k) * WIDTH + j] {
-            break;
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+30373
+25512
+65332
+33549
+35390";
+
+    #[test]
+    fn part1_example() {
+        let input = Day08::parse(EXAMPLE).unwrap();
+        assert_eq!(Day08::part1(&input).unwrap(), 21);
     }
 
-    // Moving B-T
-    for k in 1..(i + 1) {
-        b += 1;
-        if h <= x[(i - k) * WIDTH + j] {
-            break;
-        }
+    #[test]
+    fn part2_example() {
+        let input = Day08::parse(EXAMPLE).unwrap();
+        assert_eq!(Day08::part2(&input).unwrap(), 8);
     }
-
-    l * r * t * b
 }
@@ -1,15 +1,21 @@
 //! Snaking ropes
 
-use std::{collections::HashSet, fs::read_to_string};
+use std::collections::HashSet;
+
+use crate::error::ParseError;
+use crate::parse::{integer, normalise};
+use crate::solution::{Solution, Solved};
+
+type Move = ((i32, i32), usize);
 
 /// Convert a step character into a vector (dx, dy)
-fn step(x: &str) -> (i32, i32) {
+fn step(x: &str) -> Option<(i32, i32)> {
     match x {
-        "U" => (0, 1),
-        "D" => (0, -1),
-        "L" => (-1, 0),
-        "R" => (1, 0),
-        _ => unreachable!(),
+        "U" => Some((0, 1)),
+        "D" => Some((0, -1)),
+        "L" => Some((-1, 0)),
+        "R" => Some((1, 0)),
+        _ => None,
     }
 }
 
@@ -27,45 +33,108 @@ fn catchup((xh, yh): (i32, i32), (xt, yt): (i32, i32)) -> (i32, i32) {
 
 /// Return the number of locations visited by the tail of a rope with a given
 /// number of knots whose head follows a given set of directions.
-fn snake(knots: usize) -> usize {
-    let directions: String = read_to_string("data/day09.txt").unwrap();
-
+fn snake(directions: &[Move], knots: usize) -> usize {
     let (mut x, mut y) = (0, 0); // Head position
     let mut rope = vec![(0, 0); knots - 1]; // Remaining knots
 
     let mut visited: HashSet<(i32, i32)> = HashSet::new();
     visited.insert((x, y));
 
-    for l in directions.lines() {
-        if let Some((d, s)) = l.split_once(' ') {
-            let (dx, dy) = step(d);
-            let nsteps = s.parse::<usize>().unwrap();
-            for _ in 0..nsteps {
-                x += dx;
-                y += dy;
-
-                rope = rope
-                    .iter()
-                    .scan((x, y), |state, &(u, v)| {
-                        *state = catchup(*state, (u, v));
-                        Some(*state)
-                    })
-                    .collect();
-
-                visited.insert(*rope.last().unwrap());
-            }
+    for &((dx, dy), nsteps) in directions {
+        for _ in 0..nsteps {
+            x += dx;
+            y += dy;
+
+            rope = rope
+                .iter()
+                .scan((x, y), |state, &(u, v)| {
+                    *state = catchup(*state, (u, v));
+                    Some(*state)
+                })
+                .collect();
+
+            visited.insert(*rope.last().unwrap());
         }
     }
 
     visited.len()
 }
 
-/// Returns the number of places visited by T in a two-knot rope
-pub fn part1() -> usize {
-    snake(2)
+pub struct Day09;
+
+impl Solution for Day09 {
+    type Input = Vec<Move>;
+    type Output1 = usize;
+    type Output2 = usize;
+
+    /// Returns each move of the head as a step vector and a number of steps
+    fn parse(input: &str) -> Result<Vec<Move>, ParseError> {
+        let input = &normalise(input);
+
+        input
+            .lines()
+            .filter(|l| !l.is_empty())
+            .map(|l| {
+                let (d, s) = l
+                    .split_once(' ')
+                    .ok_or_else(|| ParseError::after(input, l, "a number of steps"))?;
+                let step = step(d).ok_or_else(|| ParseError::at(input, d, "U, D, L or R"))?;
+
+                Ok((step, integer(input, s)?))
+            })
+            .collect()
+    }
+
+    /// Returns the number of places visited by T in a two-knot rope
+    fn part1(directions: &Vec<Move>) -> Solved<usize> {
+        Ok(snake(directions, 2))
+    }
+
+    /// Returns the number of places visited by T in a ten-knot rope
+    fn part2(directions: &Vec<Move>) -> Solved<usize> {
+        Ok(snake(directions, 10))
+    }
 }
 
-/// Returns the number of places visited by T in a ten-knot rope
-pub fn part2() -> usize {
-    snake(10)
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+R 4
+U 4
+L 3
+D 1
+R 4
+D 1
+L 5
+R 2";
+
+    const LARGER_EXAMPLE: &str = "\
+R 5
+U 8
+L 8
+D 3
+R 17
+D 10
+L 25
+U 20";
+
+    #[test]
+    fn part1_example() {
+        let input = Day09::parse(EXAMPLE).unwrap();
+        assert_eq!(Day09::part1(&input).unwrap(), 13);
+    }
+
+    #[test]
+    fn part2_example() {
+        let input = Day09::parse(EXAMPLE).unwrap();
+        assert_eq!(Day09::part2(&input).unwrap(), 1);
+    }
+
+    #[test]
+    fn part2_larger_example() {
+        let input = Day09::parse(LARGER_EXAMPLE).unwrap();
+        assert_eq!(Day09::part2(&input).unwrap(), 36);
+    }
 }
@@ -1,55 +1,275 @@
-use std::fs::read_to_string;
-
-/// Returns a vector representing the register after each cycle
-fn parse() -> Vec<i32> {
-    let input = read_to_string("data/day10.txt").unwrap();
-    let mut register: Vec<i32> = vec![1];
-
-    for op in input.lines().map(|l| l.split_once(' ')) {
-        let prev = *register.last().unwrap();
-        register.push(prev);
-        if let Some((_, x)) = op {
-            register.push(x.parse::<i32>().unwrap() + prev)
-        }
+//! Cathode-Ray Tube
+//!
+//! The program is run on the emulated `Cpu`, which yields the registers during
+//! every cycle. That's what both parts need, since they care about the sprite
+//! position *during* each cycle rather than after it.
+
+use crate::cpu::{Cpu, Instruction};
+use crate::error::ParseError;
+use crate::grid::Grid;
+use crate::ocr;
+use crate::parse::normalise;
+use crate::picture::{Cell, Colour, Picture};
+use crate::solution::{Solution, Solved};
+
+const WIDTH: usize = 40;
+const HEIGHT: usize = ocr::HEIGHT;
+
+const LIT: char = '#';
+const DIM: char = '.';
+const GLOW: Colour = [255, 204, 0];
+const DARK: Colour = [16, 16, 32];
+
+pub struct Day10;
+
+impl Solution for Day10 {
+    type Input = Vec<Instruction>;
+    type Output1 = i32;
+    type Output2 = String;
+
+    /// Returns the decoded program
+    fn parse(input: &str) -> Result<Vec<Instruction>, ParseError> {
+        let input = &normalise(input);
+
+        input.lines().map(|line| Instruction::parse(input, line)).collect()
+    }
+
+    /// Returns the sum of the six 'signal strengths'
+    fn part1(program: &Vec<Instruction>) -> Solved<i32> {
+        let sprite = sprite(program);
+        let strengths = sprite
+            .iter()
+            .enumerate()
+            .skip(19)
+            .step_by(40)
+            .map(|(i, x)| (i as i32 + 1) * x);
+
+        Ok(strengths.sum())
     }
 
-    register.pop(); // Last record not needed.
-    register
+    /// Returns the letters drawn on the CRT screen, or an error if any of them
+    /// can't be read
+    fn part2(program: &Vec<Instruction>) -> Solved<String> {
+        Ok(ocr::read(&screen(&sprite(program)))?)
+    }
+
+    /// Draws the CRT screen for part two
+    fn render(program: &Vec<Instruction>, part: u8) -> Option<Picture> {
+        if part != 2 {
+            return None;
+        }
+
+        let cells = screen(&sprite(program))
+            .iter()
+            .map(|(_, c)| Cell::new(c, if c == LIT { GLOW } else { DARK }))
+            .collect();
+
+        Some(Picture::new(cells, HEIGHT))
+    }
 }
 
-/// Returns the sum of the six 'signal strengths'
-pub fn part1() -> i32 {
-    parse()
-        .iter()
-        .enumerate()
-        .skip(19)
-        .step_by(40)
-        .map(|(i, x)| (i as i32 + 1) * x)
-        .sum()
+/// Returns the position of the sprite, held in register `x`, during each cycle
+fn sprite(program: &[Instruction]) -> Vec<i32> {
+    Cpu::new(program)
+        .with_register('x', 1)
+        .trace()
+        .map(|tick| tick.get('x'))
+        .collect()
 }
 
-/// Prints the CRT Screen
-pub fn part2() -> String {
-    let lit: Vec<char> = parse()
-        .iter()
-        .enumerate()
-        .map(|(i, x)| pixel(i as i32, *x))
+/// Returns the image drawn on the CRT screen, which is `WIDTH` pixels wide and
+/// `HEIGHT` high. Pixels after the program has finished stay dark.
+fn screen(register: &[i32]) -> Grid<char> {
+    let pixels = (0..(WIDTH * HEIGHT))
+        .map(|i| register.get(i).map_or(DIM, |&x| pixel(i as i32, x)))
         .collect();
 
-    println!("\nCRT Screen:");
-    for line in lit.chunks(40) {
-        println!("{}", line.iter().collect::<String>())
-    }
-    println!(" ");
-
-    String::from("See above")
+    Grid::new(pixels, HEIGHT)
 }
 
 fn pixel(cursor: i32, sprite: i32) -> char {
-    let cursor = cursor % 40;
+    let cursor = cursor % WIDTH as i32;
     if (sprite - 1) <= cursor && cursor <= (sprite + 1) {
-        '#'
+        LIT
     } else {
-        '.'
+        DIM
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+addx 15
+addx -11
+addx 6
+addx -3
+addx 5
+addx -1
+addx -8
+addx 13
+addx 4
+noop
+addx -1
+addx 5
+addx -1
+addx 5
+addx -1
+addx 5
+addx -1
+addx 5
+addx -1
+addx -35
+addx 1
+addx 24
+addx -19
+addx 1
+addx 16
+addx -11
+noop
+noop
+addx 21
+addx -15
+noop
+noop
+addx -3
+addx 9
+addx 1
+addx -3
+addx 8
+addx 1
+addx 5
+noop
+noop
+noop
+noop
+noop
+addx -36
+noop
+addx 1
+addx 7
+noop
+noop
+noop
+addx 2
+addx 6
+noop
+noop
+noop
+noop
+noop
+addx 1
+noop
+noop
+addx 7
+addx 1
+noop
+addx -13
+addx 13
+addx 7
+noop
+addx 1
+addx -33
+noop
+noop
+noop
+addx 2
+noop
+noop
+noop
+addx 8
+noop
+addx -1
+addx 2
+addx 1
+noop
+addx 17
+addx -9
+addx 1
+addx 1
+addx -3
+addx 11
+noop
+noop
+addx 1
+noop
+addx 1
+noop
+noop
+addx -13
+addx -19
+addx 1
+addx 3
+addx 26
+addx -30
+addx 12
+addx -1
+addx 3
+addx 1
+noop
+noop
+noop
+addx -9
+addx 18
+addx 1
+addx 2
+noop
+noop
+addx 9
+noop
+noop
+noop
+addx -1
+addx 2
+addx -37
+addx 1
+addx 3
+noop
+addx 15
+addx -21
+addx 22
+addx -6
+addx 1
+noop
+addx 2
+addx 1
+noop
+addx -10
+noop
+noop
+addx 20
+addx 1
+addx 2
+addx 2
+addx -6
+addx -11
+noop
+noop
+noop";
+
+    const IMAGE: &str = "\
+##..##..##..##..##..##..##..##..##..##..
+###...###...###...###...###...###...###.
+####....####....####....####....####....
+#####.....#####.....#####.....#####.....
+######......######......######......####
+#######.......#######.......#######.....";
+
+    #[test]
+    fn part1_example() {
+        let input = Day10::parse(EXAMPLE).unwrap();
+        assert_eq!(Day10::part1(&input).unwrap(), 13140);
+    }
+
+    #[test]
+    fn part2_example() {
+        let input = Day10::parse(EXAMPLE).unwrap();
+        let screen = screen(&sprite(&input));
+        assert_eq!(screen.to_string(), IMAGE);
+
+        // The example's screen isn't made of letters
+        assert_eq!(ocr::read(&screen).map_err(|e| e.index), Err(0));
+        assert!(Day10::part2(&input).is_err());
     }
 }
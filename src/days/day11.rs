@@ -1,40 +1,86 @@
 //! Monkey business
 //!
-//! We can keep our relief manageable in part two by dividing through by
-//! the least common multiple (lcm). Fortunately, each monkey has a prime
-//! 'divisor' so the lcm is just the product!
+//! We can keep our relief manageable in part two by working modulo the least
+//! common multiple (lcm) of the monkeys' divisors, which doesn't change
+//! whether any worry level is divisible by any of them.
 //!
-//! Probably the distribution of items across the monkeys repeats
-//! so there's a decent chance we could speed things up dramatically
-//! using caching.
+//! Worry levels are tracked in 64 bits with checked arithmetic. If they ever
+//! overflow, the monkeys start over in 128 bits, and if even that overflows
+//! the part fails with a description of where it happened rather than a
+//! wrong number.
+//!
+//! Each monkey's operation is parsed into a small expression tree, so it can
+//! use any of `+ - * /`, brackets, and `old` or a number on either side of an
+//! operator. Under a modulus, the expression is reduced after every operation
+//! so the worry levels never grow past it. That only keeps the remainders
+//! right for `+` and `*`, so if any monkey subtracts or divides, part two
+//! tracks the exact worry levels instead. Either way, a worry level which
+//! would go below zero is an error rather than being clamped or wrapped.
+//!
+//! Without relief, each item moves from monkey to monkey regardless of the
+//! others, and under the modulus there are only so many (monkey, worry) states
+//! it can be in. So rather than simulating every round, part two follows each
+//! item until it's back in a state it started an earlier round in. From then
+//! on its path repeats, so its inspections over any number of rounds can be
+//! counted from a single cycle. Exact worry levels may never repeat, in which
+//! case they're followed until they overflow. The number of rounds can be set
+//! with `--param rounds=N`.
+
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::hash::Hash;
+use std::ops::Rem;
 
-use std::fs::read_to_string;
+use crate::error::ParseError;
+use crate::parse::{blocks, integer, normalise, Pattern};
+use crate::solution::{Solution, Solved};
 
 // Data class -----------------------------------------------------------------
 /// A monkey holding items which it inspects, throws and catches
-struct Monkey {
-    items: Vec<u64>,
-    increment: u64,
-    factor: u64,
-    power: u32,
+#[derive(Clone)]
+pub struct Monkey<W = u64> {
+    items: Vec<W>,
+    operation: Expr,
     divisor: u64,
     partners: (usize, usize),
     clock: u64,
 }
 
 impl Monkey {
-    /// Update worry level for each item
-    fn update(&mut self, relief: impl Fn(u64) -> u64) {
+    /// Returns the monkey with its worry levels tracked in a `W`
+    fn widen<W: Worry>(&self) -> Monkey<W> {
+        Monkey {
+            items: self.items.iter().map(|&x| W::from_u64(x)).collect(),
+            operation: self.operation.clone(),
+            divisor: self.divisor,
+            partners: self.partners,
+            clock: self.clock,
+        }
+    }
+}
+
+impl<W: Worry> Monkey<W> {
+    /// Update worry level for each item, working modulo `modulus` if given.
+    /// Returns why if a worry level can't be worked out.
+    fn update(&mut self, modulus: Option<W>, relief: W) -> Result<(), Fault> {
         for i in 0..self.items.len() {
             self.clock += 1;
-            self.items[i] = relief(self.items[i].pow(self.power) * self.factor + self.increment);
+            self.items[i] = self
+                .operation
+                .eval(self.items[i], modulus)?
+                .checked_div(relief)
+                .ok_or(Fault::DivisionByZero)?;
         }
+
+        Ok(())
     }
 
     /// Return a tuple containing an item and a usize representing another monkey
-    fn throw(&mut self) -> Option<(usize, u64)> {
+    fn throw(&mut self) -> Option<(usize, W)> {
         let item = self.items.pop()?;
-        let partner = if item % self.divisor == 0 {
+        let partner = if item % W::from_u64(self.divisor) == W::from_u64(0) {
             self.partners.0
         } else {
             self.partners.1
@@ -44,101 +90,706 @@ impl Monkey {
     }
 
     /// Add an item to the monkeys items
-    fn catch(&mut self, item: u64) {
+    fn catch(&mut self, item: W) {
         self.items.push(item)
     }
 }
 
+// Worry levels ---------------------------------------------------------------
+/// An unsigned integer type which worry levels can be tracked in
+pub trait Worry: Copy + Eq + Hash + Rem<Output = Self> {
+    fn from_u64(x: u64) -> Self;
+    fn from_u128(x: u128) -> Option<Self>;
+    fn checked_add(self, y: Self) -> Option<Self>;
+    fn checked_sub(self, y: Self) -> Option<Self>;
+    fn checked_mul(self, y: Self) -> Option<Self>;
+    fn checked_div(self, y: Self) -> Option<Self>;
+}
+
+macro_rules! worry {
+    ($($t:ty),*) => {
+        $(impl Worry for $t {
+            fn from_u64(x: u64) -> Self {
+                x as $t
+            }
+
+            fn from_u128(x: u128) -> Option<Self> {
+                x.try_into().ok()
+            }
+
+            fn checked_add(self, y: Self) -> Option<Self> {
+                <$t>::checked_add(self, y)
+            }
+
+            fn checked_sub(self, y: Self) -> Option<Self> {
+                <$t>::checked_sub(self, y)
+            }
+
+            fn checked_mul(self, y: Self) -> Option<Self> {
+                <$t>::checked_mul(self, y)
+            }
+
+            fn checked_div(self, y: Self) -> Option<Self> {
+                <$t>::checked_div(self, y)
+            }
+        })*
+    };
+}
+
+worry!(u64, u128);
+
+/// Worry levels which couldn't be tracked
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Overflow {
+    /// The least common multiple of the divisors doesn't fit in 128 bits
+    Modulus,
+    /// A monkey's operation couldn't give a new worry level
+    Worry { round: u64, monkey: usize, fault: Fault },
+    /// The busiest monkeys' inspections multiply to more than 128 bits
+    Inspections,
+}
+
+impl fmt::Display for Overflow {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Overflow::Modulus => write!(f, "the least common multiple of the divisors is too large"),
+            Overflow::Worry { round, monkey, fault } => {
+                write!(f, "worry levels {} in round {} during monkey {}'s turn", fault, round, monkey)
+            }
+            Overflow::Inspections => write!(f, "the level of monkey business is too large"),
+        }
+    }
+}
+
+impl Error for Overflow {}
+
+/// Why an operation couldn't give a new worry level
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    /// The worry level didn't fit in 128 bits
+    Overflow,
+    /// The worry level went below zero
+    Underflow,
+    DivisionByZero,
+    /// The operation subtracts or divides, which can't be done modulo the lcm
+    Modular,
+}
+
+impl fmt::Display for Fault {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Fault::Overflow => write!(f, "overflowed"),
+            Fault::Underflow => write!(f, "went below zero"),
+            Fault::DivisionByZero => write!(f, "were divided by zero"),
+            Fault::Modular => write!(f, "were subtracted from or divided modulo the lcm"),
+        }
+    }
+}
+
+/// Returns the least common multiple of the values, or `None` if it doesn't
+/// fit in 128 bits
+fn lcm(values: impl Iterator<Item = u64>) -> Option<u128> {
+    values.map(u128::from).try_fold(1, |lcm, x| (lcm / gcd(lcm, x)).checked_mul(x))
+}
+
+fn gcd(mut a: u128, mut b: u128) -> u128 {
+    while b > 0 {
+        (a, b) = (b, a % b);
+    }
+
+    a
+}
+
 // Parsing --------------------------------------------------------------------
-/// Returns a vector of monkeys
-fn parse() -> Vec<Monkey> {
-    let input = read_to_string("data/day11.txt").unwrap();
-    let mut monkeys = Vec::new();
-
-    for block in input.split("\n\r\n") {
-        let mut bl = block.lines();
-        bl.next(); // Skip "Monkey: " line
-
-        let items = _parse_items(bl.next().unwrap());
-        let (increment, factor, power) = _parse_operation(bl.next().unwrap());
-        let divisor = _parse_line(bl.next().unwrap());
-        let p1 = _parse_line(bl.next().unwrap()) as usize;
-        let p2 = _parse_line(bl.next().unwrap()) as usize;
-
-        monkeys.push(Monkey {
-            items,
-            increment,
-            factor,
-            power,
-            divisor,
-            partners: (p1, p2),
-            clock: 0,
-        })
-    }
-
-    monkeys
-}
-
-/// Returns a deque containing items
-fn _parse_items(l: &str) -> Vec<u64> {
-    l.replace("Starting items:", "")
-        .split(',')
-        .map(|num| num.trim().parse::<u64>().unwrap())
-        .collect()
-}
-
-/// Returns a tuple representing the increment and factor
-fn _parse_operation(l: &str) -> (u64, u64, u32) {
-    let add = l.contains('+');
-    let pow = l.contains("old * old");
-
-    if pow {
-        (0, 1, 2)
-    } else {
-        let term = _parse_line(l);
-        if add {
-            (term, 1, 1)
-        } else {
-            (0, term, 1)
+const MONKEY: &str = "\
+Monkey {index}:
+  Starting items: {items}
+  Operation: new = {operation}
+  Test: divisible by {divisor}
+    If true: throw to monkey {yes}
+    If false: throw to monkey {no}";
+
+/// Returns the items in a comma separated list
+fn _parse_items(input: &str, items: &str) -> Result<Vec<u64>, ParseError> {
+    items.split(", ").map(|item| integer(input, item)).collect()
+}
+
+// Operations -----------------------------------------------------------------
+/// An expression giving the new worry level of an item from the `old` one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    Old,
+    Literal(u64),
+    Binary(Box<Expr>, Operator, Box<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operator {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+impl Expr {
+    /// Parses `text`, a slice of `input`. `*` and `/` bind more tightly than
+    /// `+` and `-`, operators of the same precedence apply left to right, and
+    /// brackets group.
+    fn parse(input: &str, text: &str) -> Result<Expr, ParseError> {
+        let tokens = _tokens(text);
+        let mut pos = 0;
+        let expr = _parse_sum(input, text, &tokens, &mut pos)?;
+
+        match tokens.get(pos) {
+            Some(token) => Err(ParseError::at(input, token, "an operator")),
+            None => Ok(expr),
+        }
+    }
+
+    /// Returns the value of the expression for the given `old` value. With a
+    /// modulus, everything is reduced by it after each operation, which only
+    /// keeps the remainder right for `+` and `*`, so `-` and `/` are refused.
+    ///
+    /// Returns why if the value overflows a `W`, goes below zero or is divided
+    /// by zero.
+    fn eval<W: Worry>(&self, old: W, modulus: Option<W>) -> Result<W, Fault> {
+        let reduce = |x: W| modulus.map_or(x, |m| x % m);
+
+        match self {
+            Expr::Old => Ok(reduce(old)),
+            Expr::Literal(n) => Ok(reduce(W::from_u64(*n))),
+            Expr::Binary(lhs, operator, rhs) => {
+                let (a, b) = (lhs.eval(old, modulus)?, rhs.eval(old, modulus)?);
+                let x = match operator {
+                    Operator::Add => a.checked_add(b).ok_or(Fault::Overflow)?,
+                    Operator::Mul => a.checked_mul(b).ok_or(Fault::Overflow)?,
+                    _ if modulus.is_some() => return Err(Fault::Modular),
+                    Operator::Sub => a.checked_sub(b).ok_or(Fault::Underflow)?,
+                    Operator::Div => a.checked_div(b).ok_or(Fault::DivisionByZero)?,
+                };
+                Ok(reduce(x))
+            }
+        }
+    }
+
+    /// Returns whether the expression can be worked modulo the lcm, which is
+    /// when it only adds and multiplies
+    fn modular(&self) -> bool {
+        match self {
+            Expr::Binary(lhs, operator, rhs) => {
+                matches!(operator, Operator::Add | Operator::Mul) && lhs.modular() && rhs.modular()
+            }
+            _ => true,
         }
     }
 }
 
-/// Returns last word on line, parsed as an integer
-fn _parse_line(l: &str) -> u64 {
-    l.split_whitespace().last().unwrap().parse::<u64>().unwrap()
+/// Splits an expression into `old`, numbers, operators and brackets
+fn _tokens(text: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut rest = text.trim_start();
+
+    while let Some(c) = rest.chars().next() {
+        let len = match c.is_ascii_alphanumeric() {
+            true => rest.find(|c: char| !c.is_ascii_alphanumeric()).unwrap_or(rest.len()),
+            false => c.len_utf8(),
+        };
+        tokens.push(&rest[..len]);
+        rest = rest[len..].trim_start();
+    }
+
+    tokens
+}
+
+/// Parses terms joined by `+` and `-`
+fn _parse_sum(input: &str, text: &str, tokens: &[&str], pos: &mut usize) -> Result<Expr, ParseError> {
+    let mut expr = _parse_product(input, text, tokens, pos)?;
+    while let Some(operator) = tokens.get(*pos).and_then(|t| _operator(t, "+-")) {
+        *pos += 1;
+        let rhs = _parse_product(input, text, tokens, pos)?;
+        expr = Expr::Binary(Box::new(expr), operator, Box::new(rhs));
+    }
+
+    Ok(expr)
+}
+
+/// Parses factors joined by `*` and `/`
+fn _parse_product(input: &str, text: &str, tokens: &[&str], pos: &mut usize) -> Result<Expr, ParseError> {
+    let mut expr = _parse_factor(input, text, tokens, pos)?;
+    while let Some(operator) = tokens.get(*pos).and_then(|t| _operator(t, "*/")) {
+        *pos += 1;
+        let rhs = _parse_factor(input, text, tokens, pos)?;
+        expr = Expr::Binary(Box::new(expr), operator, Box::new(rhs));
+    }
+
+    Ok(expr)
+}
+
+/// Parses `old`, a number or a bracketed expression
+fn _parse_factor(input: &str, text: &str, tokens: &[&str], pos: &mut usize) -> Result<Expr, ParseError> {
+    let expected = "`old`, a number or `(`";
+    let token = *tokens
+        .get(*pos)
+        .ok_or_else(|| ParseError::after(input, text, expected))?;
+    *pos += 1;
+
+    match token {
+        "old" => Ok(Expr::Old),
+        "(" => {
+            let expr = _parse_sum(input, text, tokens, pos)?;
+            match tokens.get(*pos) {
+                Some(&")") => {
+                    *pos += 1;
+                    Ok(expr)
+                }
+                Some(token) => Err(ParseError::at(input, token, "`)`")),
+                None => Err(ParseError::after(input, text, "`)`")),
+            }
+        }
+        _ if token.starts_with(|c: char| c.is_ascii_digit()) => Ok(Expr::Literal(integer(input, token)?)),
+        _ => Err(ParseError::at(input, token, expected)),
+    }
+}
+
+/// Returns the operator for `token` if it's one of `allowed`
+fn _operator(token: &str, allowed: &str) -> Option<Operator> {
+    let operator = match token {
+        "+" => Operator::Add,
+        "-" => Operator::Sub,
+        "*" => Operator::Mul,
+        "/" => Operator::Div,
+        _ => return None,
+    };
+
+    allowed.contains(token).then_some(operator)
 }
 
 // Solutions ------------------------------------------------------------------
 
 /// Conduct monkey business, redistributing items across the monkeys
-fn monkey_business(monkeys: &mut Vec<Monkey>, rounds: usize, relief: impl Fn(u64) -> u64) {
-    for _ in 0..rounds {
+fn monkey_business<W: Worry>(
+    monkeys: &mut [Monkey<W>],
+    rounds: usize,
+    modulus: Option<W>,
+    relief: W,
+) -> Result<(), Overflow> {
+    for round in 1..=rounds as u64 {
         for i in 0..monkeys.len() {
-            monkeys[i].update(&relief);
+            monkeys[i]
+                .update(modulus, relief)
+                .map_err(|fault| Overflow::Worry { round, monkey: i, fault })?;
             while let Some((j, item)) = monkeys[i].throw() {
                 monkeys[j].catch(item)
             }
         }
     }
+
+    Ok(())
 }
 
-/// Returns the level of monkey business after 20 rounds when operating with relief
-pub fn part1() -> u64 {
-    let mut monkeys = parse();
-    monkey_business(&mut monkeys, 20, |x| x / 3);
-    monkeys.sort_by(|a, b| b.clock.cmp(&a.clock));
-    monkeys[0].clock * monkeys[1].clock
+/// Returns the level of monkey business after the given number of rounds,
+/// dividing worry levels by `relief` after each inspection and working modulo
+/// the lcm of the divisors if asked
+fn level(monkeys: &[Monkey], rounds: usize, relief: u64, modular: bool) -> Result<u128, Overflow> {
+    let modulus = match modular {
+        true => Some(lcm(monkeys.iter().map(|m| m.divisor)).ok_or(Overflow::Modulus)?),
+        false => None,
+    };
+
+    let clocks = match _clocks::<u64>(monkeys, rounds, relief, modulus) {
+        Ok(clocks) => clocks,
+        Err(_) => _clocks::<u128>(monkeys, rounds, relief, modulus)?,
+    };
+
+    Ok(clocks[0] as u128 * clocks[1] as u128)
 }
 
-/// Returns the level of monkey business after 10,000 rounds when operating without relief
-pub fn part2() -> u128 {
-    let mut monkeys = parse();
+/// Returns how many items each monkey inspected, busiest first, with worry
+/// levels tracked in a `W`
+fn _clocks<W: Worry>(monkeys: &[Monkey], rounds: usize, relief: u64, modulus: Option<u128>) -> Result<Vec<u64>, Overflow> {
+    let modulus = _narrow(modulus)?;
+    let mut monkeys: Vec<Monkey<W>> = monkeys.iter().map(|m| m.widen()).collect();
+    monkey_business(&mut monkeys, rounds, modulus, W::from_u64(relief))?;
+
+    let mut clocks: Vec<u64> = monkeys.iter().map(|m| m.clock).collect();
+    clocks.sort_by_key(|&c| Reverse(c));
+    Ok(clocks)
+}
+
+/// Returns the modulus as a `W`
+fn _narrow<W: Worry>(modulus: Option<u128>) -> Result<Option<W>, Overflow> {
+    modulus.map(|m| W::from_u128(m).ok_or(Overflow::Modulus)).transpose()
+}
 
-    // monkey divisors are prime, so lcm is product
-    let lcm: u64 = monkeys.iter().map(|m| m.divisor).product();
+/// Returns the level of monkey business after the given number of rounds
+/// without relief, counting each item's inspections from the cycle its path
+/// falls into. Works modulo the lcm of the divisors unless an operation
+/// subtracts or divides.
+fn extrapolated_level(monkeys: &[Monkey], rounds: u64) -> Result<u128, Overflow> {
+    let modulus = match monkeys.iter().all(|m| m.operation.modular()) {
+        true => Some(lcm(monkeys.iter().map(|m| m.divisor)).ok_or(Overflow::Modulus)?),
+        false => None,
+    };
 
-    monkey_business(&mut monkeys, 10_000, |x| x % lcm);
-    monkeys.sort_by(|a, b| b.clock.cmp(&a.clock));
-    (monkeys[0].clock as u128) * (monkeys[1].clock as u128)
+    let mut counts = match _inspections::<u64>(monkeys, rounds, modulus) {
+        Ok(counts) => counts,
+        Err(_) => _inspections::<u128>(monkeys, rounds, modulus)?,
+    };
+
+    counts.sort_by_key(|&c| Reverse(c));
+    counts[0].checked_mul(counts[1]).ok_or(Overflow::Inspections)
+}
+
+/// Returns how many items each monkey inspects over the given number of
+/// rounds, with worry levels tracked in a `W`, modulo `modulus` if given
+fn _inspections<W: Worry>(monkeys: &[Monkey], rounds: u64, modulus: Option<u128>) -> Result<Vec<u128>, Overflow> {
+    let modulus = _narrow(modulus)?;
+    let monkeys: Vec<Monkey<W>> = monkeys.iter().map(|m| m.widen()).collect();
+
+    let mut counts = vec![0; monkeys.len()];
+    for (i, monkey) in monkeys.iter().enumerate() {
+        for &item in &monkey.items {
+            let worry = modulus.map_or(item, |m| item % m);
+            let trajectory = _trajectory(&monkeys, (i, worry), rounds, modulus)?;
+            counts.iter_mut().zip(trajectory).for_each(|(c, t)| *c += t);
+        }
+    }
+
+    Ok(counts)
+}
+
+/// Returns how many times each monkey inspects an item over the given number
+/// of rounds, starting from the given (monkey, worry) state
+fn _trajectory<W: Worry>(
+    monkeys: &[Monkey<W>],
+    (mut monkey, mut worry): (usize, W),
+    rounds: u64,
+    modulus: Option<W>,
+) -> Result<Vec<u128>, Overflow> {
+    let mut seen = HashMap::new(); // Round in which each state was first seen
+    let mut inspections = Vec::new(); // Monkey making each inspection, in order
+    let mut starts = Vec::new(); // Index into `inspections` at the start of each round
+
+    let tally = |inspections: &[usize]| {
+        let mut counts = vec![0; monkeys.len()];
+        inspections.iter().for_each(|&m| counts[m] += 1);
+        counts
+    };
+
+    for round in 0.. {
+        starts.push(inspections.len());
+        if round == rounds {
+            return Ok(tally(&inspections));
+        }
+
+        if let Some(&first) = seen.get(&(monkey, worry)) {
+            let (length, offset) = (round - first, starts[first as usize]);
+            let (repeats, rest) = ((rounds - first) / length, (rounds - first) % length);
+
+            let head = tally(&inspections[..offset]);
+            let cycle = tally(&inspections[offset..]);
+            let tail = tally(&inspections[offset..starts[(first + rest) as usize]]);
+
+            return Ok((0..monkeys.len())
+                .map(|m| head[m] + repeats as u128 * cycle[m] + tail[m])
+                .collect());
+        }
+        seen.insert((monkey, worry), round);
+
+        // Thrown items are only inspected again this round by later monkeys
+        loop {
+            inspections.push(monkey);
+            let current = &monkeys[monkey];
+            worry = current
+                .operation
+                .eval(worry, modulus)
+                .map_err(|fault| Overflow::Worry { round: round + 1, monkey, fault })?;
+
+            let next = match worry % W::from_u64(current.divisor) == W::from_u64(0) {
+                true => current.partners.0,
+                false => current.partners.1,
+            };
+            let later = next > monkey;
+            monkey = next;
+            if !later {
+                break;
+            }
+        }
+    }
+
+    unreachable!()
+}
+
+/// The monkeys, along with the number of rounds to run in part two
+pub struct Troop {
+    monkeys: Vec<Monkey>,
+    rounds: u64,
+}
+
+pub struct Day11;
+
+impl Solution for Day11 {
+    type Input = Troop;
+    type Output1 = u128;
+    type Output2 = u128;
+
+    /// Returns the monkeys, with part two set to run 10,000 rounds
+    fn parse(input: &str) -> Result<Troop, ParseError> {
+        let input = &normalise(input);
+
+        let mut monkeys = Vec::new();
+        let mut targets = Vec::new();
+
+        let pattern = Pattern::new(MONKEY);
+        for block in blocks(input) {
+            let fields = pattern.parse(input, block)?;
+
+            let items = _parse_items(input, fields.slice("items"))?;
+            let operation = Expr::parse(input, fields.slice("operation"))?;
+            let divisor = fields.get("divisor")?;
+            if divisor == 0 {
+                return Err(ParseError::at(input, fields.slice("divisor"), "a divisor other than zero"));
+            }
+            let p1 = fields.get("yes")?;
+            let p2 = fields.get("no")?;
+
+            // A monkey throwing to itself would never run out of items
+            for (key, target) in [("yes", p1), ("no", p2)] {
+                if target == monkeys.len() {
+                    return Err(ParseError::at(input, fields.slice(key), "a monkey other than itself"));
+                }
+            }
+            targets.extend([(fields.slice("yes"), p1), (fields.slice("no"), p2)]);
+
+            monkeys.push(Monkey {
+                items,
+                operation,
+                divisor,
+                partners: (p1, p2),
+                clock: 0,
+            })
+        }
+
+        // Monkey business needs the two busiest monkeys
+        if monkeys.len() < 2 {
+            return Err(ParseError::eof(input, "at least two monkeys"));
+        }
+
+        // Monkeys can only throw to monkeys which are there
+        if let Some(&(text, _)) = targets.iter().find(|&&(_, i)| i >= monkeys.len()) {
+            let expected = format!("a monkey numbered below {}", monkeys.len());
+            return Err(ParseError::at(input, text, expected));
+        }
+
+        Ok(Troop { monkeys, rounds: 10_000 })
+    }
+
+    /// Returns the level of monkey business after 20 rounds when operating with relief
+    fn part1(troop: &Troop) -> Solved<u128> {
+        Ok(level(&troop.monkeys, 20, 3, false)?)
+    }
+
+    /// Returns the level of monkey business after 10,000 rounds, or as many as
+    /// asked, when operating without relief
+    fn part2(troop: &Troop) -> Solved<u128> {
+        Ok(extrapolated_level(&troop.monkeys, troop.rounds)?)
+    }
+
+    /// Sets the number of `rounds` in part two
+    fn configure(troop: &mut Troop, name: &str, value: &str) -> Result<(), String> {
+        match name {
+            "rounds" => {
+                troop.rounds = value
+                    .parse()
+                    .map_err(|_| format!("`rounds` must be a whole number, not `{}`", value))?;
+                Ok(())
+            }
+            _ => Err(format!("there's no parameter `{}`, only `rounds`", name)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+Monkey 0:
+  Starting items: 79, 98
+  Operation: new = old * 19
+  Test: divisible by 23
+    If true: throw to monkey 2
+    If false: throw to monkey 3
+
+Monkey 1:
+  Starting items: 54, 65, 75, 74
+  Operation: new = old + 6
+  Test: divisible by 19
+    If true: throw to monkey 2
+    If false: throw to monkey 0
+
+Monkey 2:
+  Starting items: 79, 60, 97
+  Operation: new = old * old
+  Test: divisible by 13
+    If true: throw to monkey 1
+    If false: throw to monkey 3
+
+Monkey 3:
+  Starting items: 74
+  Operation: new = old + 3
+  Test: divisible by 17
+    If true: throw to monkey 0
+    If false: throw to monkey 1";
+
+    #[test]
+    fn part1_example() {
+        let input = Day11::parse(EXAMPLE).unwrap();
+        assert_eq!(Day11::part1(&input).unwrap(), 10605);
+    }
+
+    #[test]
+    fn part2_example() {
+        let input = Day11::parse(EXAMPLE).unwrap();
+        assert_eq!(Day11::part2(&input).unwrap(), 2713310158);
+    }
+
+    #[test]
+    fn cycles() {
+        let mut troop = Day11::parse(EXAMPLE).unwrap();
+        for rounds in [0, 1, 20, 1000, 4321] {
+            assert_eq!(extrapolated_level(&troop.monkeys, rounds), level(&troop.monkeys, rounds as usize, 1, true));
+        }
+
+        Day11::configure(&mut troop, "rounds", "1000000000000").unwrap();
+        assert_eq!(Day11::part2(&troop).unwrap(), 27142382301385558311211320);
+        assert!(Day11::configure(&mut troop, "round", "10").is_err());
+        assert!(Day11::configure(&mut troop, "rounds", "-1").is_err());
+    }
+
+    #[test]
+    fn exact_worry() {
+        // Halving doesn't survive working modulo the lcm, so part two mustn't
+        let monkeys = Day11::parse("\
+Monkey 0:
+  Starting items: 7, 10
+  Operation: new = old * 3 / 2
+  Test: divisible by 2
+    If true: throw to monkey 1
+    If false: throw to monkey 1
+
+Monkey 1:
+  Starting items: 5
+  Operation: new = old + 1
+  Test: divisible by 3
+    If true: throw to monkey 0
+    If false: throw to monkey 0").unwrap().monkeys;
+
+        for rounds in [3, 6, 10] {
+            assert_eq!(extrapolated_level(&monkeys, rounds), level(&monkeys, rounds as usize, 1, false));
+        }
+        let fault = Overflow::Worry { round: 1, monkey: 0, fault: Fault::Modular };
+        assert_eq!(level(&monkeys, 3, 1, true), Err(fault));
+
+        // Worry levels can't go below zero, with or without relief
+        let monkeys = Day11::parse("\
+Monkey 0:
+  Starting items: 150
+  Operation: new = old - 100
+  Test: divisible by 2
+    If true: throw to monkey 1
+    If false: throw to monkey 1
+
+Monkey 1:
+  Starting items: 1
+  Operation: new = old * 2
+  Test: divisible by 5
+    If true: throw to monkey 0
+    If false: throw to monkey 0").unwrap().monkeys;
+
+        let fault = |round| Err(Overflow::Worry { round, monkey: 0, fault: Fault::Underflow });
+        assert_eq!(level(&monkeys, 5, 3, false), fault(2));
+        assert_eq!(extrapolated_level(&monkeys, 5), fault(3));
+    }
+
+    #[test]
+    fn bad_monkeys() {
+        let monkey = |divisor, target| format!("\
+Monkey 0:
+  Starting items: 1
+  Operation: new = old + 1
+  Test: divisible by {}
+    If true: throw to monkey 1
+    If false: throw to monkey {}
+
+Monkey 1:
+  Starting items: 2
+  Operation: new = old * 2
+  Test: divisible by 3
+    If true: throw to monkey 0
+    If false: throw to monkey 0", divisor, target);
+
+        assert!(Day11::parse(&monkey(2, 1)).is_ok());
+
+        let error = Day11::parse(&monkey(2, 7)).err().unwrap();
+        assert_eq!((error.line, error.column), (6, 31));
+        assert_eq!(error.expected, "a monkey numbered below 2");
+
+        let error = Day11::parse(&monkey(0, 1)).err().unwrap();
+        assert_eq!((error.line, error.column), (4, 22));
+        assert_eq!(error.expected, "a divisor other than zero");
+
+        let error = Day11::parse(&monkey(2, 0)).err().unwrap();
+        assert_eq!((error.line, error.column), (6, 31));
+        assert_eq!(error.expected, "a monkey other than itself");
+
+        let error = Day11::parse(monkey(2, 1).split("\n\n").next().unwrap()).err().unwrap();
+        assert_eq!(error.expected, "at least two monkeys");
+    }
+
+    #[test]
+    fn worry_overflow() {
+        assert_eq!(lcm([4, 6, 10].into_iter()), Some(60));
+        assert_eq!(lcm([u64::MAX, u64::MAX - 1, u64::MAX - 2].into_iter()), None);
+
+        let monkeys = Day11::parse("\
+Monkey 0:
+  Starting items: 4294967296
+  Operation: new = old * old
+  Test: divisible by 2
+    If true: throw to monkey 1
+    If false: throw to monkey 1
+
+Monkey 1:
+  Starting items: 1
+  Operation: new = old + 1
+  Test: divisible by 2
+    If true: throw to monkey 0
+    If false: throw to monkey 0").unwrap().monkeys;
+
+        // 2^64 doesn't fit in 64 bits, and its square doesn't fit in 128
+        assert!(_clocks::<u64>(&monkeys, 1, 1, None).is_err());
+        assert_eq!(level(&monkeys, 1, 1, false), Ok(2));
+        let overflow = Overflow::Worry { round: 2, monkey: 0, fault: Fault::Overflow };
+        assert_eq!(level(&monkeys, 2, 1, false), Err(overflow));
+    }
+
+    #[test]
+    fn operations() {
+        let parse = |text| Expr::parse(text, text);
+
+        let expr = parse("old * (old - 3) / 2 + 10 - old").unwrap();
+        assert_eq!(expr.eval(7u64, None), Ok(7 * 4 / 2 + 10 - 7));
+        assert!(!expr.modular());
+        assert_eq!(parse("2 - old").unwrap().eval(5u64, None), Err(Fault::Underflow));
+        assert_eq!(parse("(old + 6) * old").unwrap().eval(10u64, Some(7)), Ok(160 % 7));
+        assert_eq!(parse("old - 9").unwrap().eval(14u64, Some(7)), Err(Fault::Modular));
+        assert_eq!(parse("old * old").unwrap().eval(u64::MAX, None), Err(Fault::Overflow));
+        assert_eq!(parse("old / (old - old)").unwrap().eval(3u64, None), Err(Fault::DivisionByZero));
+
+        let error = parse("old * (old + 2").unwrap_err();
+        assert_eq!((error.column, error.expected.as_str()), (15, "`)`"));
+        let error = parse("old ^ 2").unwrap_err();
+        assert_eq!((error.column, error.expected.as_str()), (5, "an operator"));
+    }
 }
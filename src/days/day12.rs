@@ -4,48 +4,73 @@
 //! the same as the distance from 'E' to 'S'. Likewise, the shortest distance
 //! from an 'a' to 'E' is the same as the shortest distance from 'E' to an 'a'.
 //!
-//! Both problems can be solved using a breadth first search.
+//! Both problems can be solved using a breadth first search. Reversing the
+//! path it finds gives the route up the hill, which can be drawn onto the
+//! height map as arrows.
+
+use std::cmp::Ordering;
 
 use crate::grid::Grid;
-use std::fs::read_to_string;
+use crate::error::ParseError;
+use crate::parse::normalise;
+use crate::picture::{Cell, Colour, Picture};
+use crate::search;
+use crate::solution::{Solution, Solved};
 
 type Point = (usize, usize);
 
-fn parse() -> (Grid<u8>, Point) {
-    let input = read_to_string("data/day12.txt").unwrap();
-    let grid = Grid::parse(&input);
-    let start = grid.find(b'E'); // Start at the end
+/// Returns the minimal distance from start to the nearest `end` through the
+/// grid, if it can be reached
+fn climb(grid: &Grid<u8>, start: Point, end: u8) -> Option<u32> {
+    let paths = search::bfs(start, search::grid_steps(grid, connected), |&(i, j)| grid.get(i, j) == end);
 
-    (grid, start.unwrap())
+    paths.goal_cost().map(|cost| cost as u32)
 }
 
-/// Returns the minimal distance from start to end through the grid
-fn bfs(grid: &mut Grid<u8>, start: Point, end: u8) -> u32 {
-    let mut queue: Vec<(usize, usize)> = vec![start];
-    let mut next: Vec<(usize, usize)> = Vec::new();
-
-    let mut visited = Grid::new(vec![false; grid.len()], grid.nrow);
-    visited.set(start.0, start.1, true);
-
-    let mut nsteps = 0;
-    loop {
-        while let Some((i, j)) = queue.pop() {
-            let curr = grid.get(i, j);
-            if curr == end {
-                return nsteps;
-            }
-
-            for (ni, nj) in grid.neighbours(i, j) {
-                if !visited.get(ni, nj) & connected(curr, grid.get(ni, nj)) {
-                    visited.set(ni, nj, true);
-                    next.push((ni, nj));
-                }
-            }
-        }
+/// Returns the shortest route from the nearest `from` up to the `end`
+fn route(grid: &Grid<u8>, end: Point, from: u8) -> Option<Vec<Point>> {
+    let paths = search::bfs(end, search::grid_steps(grid, connected), |&(i, j)| grid.get(i, j) == from);
+
+    let mut route = paths.goal_path()?;
+    route.reverse();
+    Some(route)
+}
+
+/// Returns the height map with the route drawn on it as arrows, each
+/// pointing to the next step
+fn draw(grid: &Grid<u8>, route: &[Point]) -> Picture {
+    let cells = (0..grid.nrow)
+        .flat_map(|i| (0..grid.ncol).map(move |j| (i, j)))
+        .map(|(i, j)| {
+            let x = grid.get(i, j);
+            Cell::new(x as char, terrain(x))
+        })
+        .collect();
+    let mut picture = Picture::new(cells, grid.nrow);
 
-        nsteps += 1;
-        queue.append(&mut next);
+    for step in route.windows(2) {
+        let ((i, j), (ni, nj)) = (step[0], step[1]);
+        let arrow = match (ni.cmp(&i), nj.cmp(&j)) {
+            (Ordering::Greater, _) => 'v',
+            (Ordering::Less, _) => '^',
+            (_, Ordering::Greater) => '>',
+            _ => '<',
+        };
+        picture.set(i, j, Cell::new(arrow, ROUTE));
     }
+
+    picture
+}
+
+const ROUTE: Colour = [220, 50, 40];
+
+/// Returns the colour of a height, from green in the valleys to white at
+/// the peaks
+fn terrain(x: u8) -> Colour {
+    let (low, high) = ([40, 110, 40], [245, 245, 245]);
+    let t = (height(x) - b'a') as u32;
+
+    [0, 1, 2].map(|k| ((low[k] * (25 - t) + high[k] * t) / 25) as u8)
 }
 
 /// Returns true if `x` is no more than one step higher than `y`
@@ -62,14 +87,85 @@ fn height(x: u8) -> u8 {
     }
 }
 
-/// Return the minimal number of steps from 'E' to 'S'
-pub fn part1() -> u32 {
-    let (mut grid, start) = parse();
-    bfs(&mut grid, start, b'S')
+pub struct Day12;
+
+impl Solution for Day12 {
+    type Input = (Grid<u8>, Point);
+    type Output1 = u32;
+    type Output2 = u32;
+
+    fn parse(input: &str) -> Result<(Grid<u8>, Point), ParseError> {
+        let input = &normalise(input);
+
+        if let Some(i) = input.find(|c: char| !(c.is_ascii_lowercase() || "SE\n".contains(c))) {
+            return Err(ParseError::at_byte(input, i, "a height from a to z, `S` or `E`"));
+        }
+
+        let grid = Grid::parse(input)?;
+        let start = grid.find(b'E'); // Start at the end
+
+        Ok((grid, start.ok_or_else(|| ParseError::eof(input, "an `E`"))?))
+    }
+
+    /// Return the minimal number of steps from 'E' to 'S'
+    fn part1((grid, start): &(Grid<u8>, Point)) -> Solved<u32> {
+        Ok(climb(grid, *start, b'S').ok_or("no path from S to E")?)
+    }
+
+    /// Return the minimal number of steps from 'E' to an 'a'
+    fn part2((grid, start): &(Grid<u8>, Point)) -> Solved<u32> {
+        Ok(climb(grid, *start, b'a').ok_or("no path from an a to E")?)
+    }
+
+    /// Draws the route to the `E` from the `S`, or from the nearest `a`. With
+    /// no route, which the part reports, just the height map is drawn.
+    fn render((grid, start): &(Grid<u8>, Point), part: u8) -> Option<Picture> {
+        let from = if part == 1 { b'S' } else { b'a' };
+
+        Some(draw(grid, &route(grid, *start, from).unwrap_or_default()))
+    }
 }
 
-/// Return the minimal number of steps from 'E' to an 'a'
-pub fn part2() -> u32 {
-    let (mut grid, start) = parse();
-    bfs(&mut grid, start, b'a')
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+Sabqponm
+abcryxxl
+accszExk
+acctuvwj
+abdefghi";
+
+    #[test]
+    fn part1_example() {
+        let input = Day12::parse(EXAMPLE).unwrap();
+        assert_eq!(Day12::part1(&input).unwrap(), 31);
+    }
+
+    #[test]
+    fn part2_example() {
+        let input = Day12::parse(EXAMPLE).unwrap();
+        assert_eq!(Day12::part2(&input).unwrap(), 29);
+    }
+
+    #[test]
+    fn route_example() {
+        let (grid, end) = Day12::parse(EXAMPLE).unwrap();
+        let route = route(&grid, end, b'S').unwrap();
+        assert_eq!((route[0], route[route.len() - 1]), ((0, 0), end));
+        assert_eq!(route.len(), 32);
+
+        let picture = draw(&grid, &route).to_string();
+        assert_eq!(picture.matches(|c| "<>^v".contains(c)).count(), 31);
+        assert_eq!(picture.lines().nth(2).unwrap().chars().nth(5), Some('E'));
+    }
+
+    #[test]
+    fn no_path() {
+        let input = Day12::parse("SbE").unwrap();
+        assert_eq!(Day12::part1(&input).unwrap_err().to_string(), "no path from S to E");
+        assert!(Day12::part2(&input).is_err());
+        assert_eq!(Day12::render(&input, 1).unwrap().to_string(), "SbE");
+    }
 }
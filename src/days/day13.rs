@@ -8,16 +8,11 @@
 //! lists which don't we make convert "]" to a symbol which is low on the
 //! ASCII table, namely "!".
 
-use std::{cmp::Ordering, fs::read_to_string};
-
-/// Returns the input with the appropriate transformations to ensure
-/// proper lexicographical ordering.
-fn parse() -> String {
-    read_to_string("data/day13.txt")
-        .unwrap()
-        .replace("10", ":") // Ensure 9 < 10
-        .replace(']', "!") // Ensure running out has lowest value
-}
+use std::cmp::Ordering;
+
+use crate::error::ParseError;
+use crate::parse::normalise;
+use crate::solution::{Solution, Solved};
 
 /// Return the relative ordering of `left` wrt `right`
 fn compare_packets(left: &str, right: &str) -> Ordering {
@@ -63,37 +58,110 @@ fn compare_packets(left: &str, right: &str) -> Ordering {
     }
 }
 
-/// Returns the number of 'ordered' pairs
-pub fn part1() -> i32 {
-    let input = parse();
-    let mut lists = input.lines().filter(|l| !l.is_empty());
-
-    let mut idx = 0;
-    let mut solution = 0;
-    while let Some(left) = lists.next() {
-        if let Some(right) = lists.next() {
-            idx += 1;
-            if compare_packets(left, right) == Ordering::Less {
-                solution += idx
+pub struct Day13;
+
+impl Solution for Day13 {
+    type Input = String;
+    type Output1 = i32;
+    type Output2 = usize;
+
+    /// Returns the input with the appropriate transformations to ensure
+    /// proper lexicographical ordering.
+    fn parse(input: &str) -> Result<String, ParseError> {
+        let input = &normalise(input);
+
+        if let Some(i) = input.find(|c: char| !(c.is_ascii_digit() || "[],\n".contains(c))) {
+            return Err(ParseError::at_byte(input, i, "a digit, `[`, `]` or `,`"));
+        }
+
+        let packets = input
+            .replace("10", ":") // Ensure 9 < 10
+            .replace(']', "!"); // Ensure running out has lowest value
+
+        Ok(packets)
+    }
+
+    /// Returns the number of 'ordered' pairs
+    fn part1(input: &String) -> Solved<i32> {
+        let mut lists = input.lines().filter(|l| !l.is_empty());
+
+        let mut idx = 0;
+        let mut solution = 0;
+        while let Some(left) = lists.next() {
+            if let Some(right) = lists.next() {
+                idx += 1;
+                if compare_packets(left, right) == Ordering::Less {
+                    solution += idx
+                }
             }
         }
+
+        Ok(solution)
     }
 
-    solution
+    /// Returns the 'decoder key'
+    fn part2(input: &String) -> Solved<usize> {
+        let mut input = input.clone();
+        input.push_str("\n\n[[2!!\n[[6!!");
+
+        let mut lists: Vec<&str> = input.lines().filter(|l| !l.is_empty()).collect();
+
+        lists.sort_by(|&a, &b| compare_packets(a, b));
+
+        let div1 = lists.iter().position(|&x| x == "[[2!!");
+        let div2 = lists.iter().position(|&x| x == "[[6!!");
+
+        // Using 1-indexing not 0-indexing
+        Ok((div1.unwrap() + 1) * (div2.unwrap() + 1))
+    }
 }
 
-/// Returns the 'decoder key'
-pub fn part2() -> usize {
-    let mut input = parse();
-    input.push_str("\n\n[[2!!\n[[6!!");
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+[1,1,3,1,1]
+[1,1,5,1,1]
+
+[[1],[2,3,4]]
+[[1],4]
+
+[9]
+[[8,7,6]]
+
+[[4,4],4,4]
+[[4,4],4,4,4]
 
-    let mut lists: Vec<&str> = input.lines().filter(|l| !l.is_empty()).collect();
+[7,7,7,7]
+[7,7,7]
 
-    lists.sort_by(|&a, &b| compare_packets(a, b));
+[]
+[3]
 
-    let div1 = lists.iter().position(|&x| x == "[[2!!");
-    let div2 = lists.iter().position(|&x| x == "[[6!!");
+[[[]]]
+[[]]
 
-    // Using 1-indexing not 0-indexing
-    (div1.unwrap() + 1) * (div2.unwrap() + 1)
+[1,[2,[3,[4,[5,6,7]]]],8,9]
+[1,[2,[3,[4,[5,6,0]]]],8,9]";
+
+    #[test]
+    fn part1_example() {
+        let input = Day13::parse(EXAMPLE).unwrap();
+        assert_eq!(Day13::part1(&input).unwrap(), 13);
+    }
+
+    #[test]
+    fn part2_example() {
+        let input = Day13::parse(EXAMPLE).unwrap();
+        assert_eq!(Day13::part2(&input).unwrap(), 140);
+    }
+
+    /// "10" is rewritten as ":" so that it sorts after "9"
+    #[test]
+    fn ten_sorts_after_nine() {
+        let packets = Day13::parse("[10]\n[9]").unwrap();
+        let (left, right) = packets.split_once('\n').unwrap();
+        assert_eq!(compare_packets(left, right), Ordering::Greater);
+    }
 }
@@ -1,105 +1,130 @@
 //! Falling sand
 //!
-//! Examining the input, we see that all the x-coordinates fall in the range
-//! 400-600 and all the y-coordinates fall in the range 0-200. This means
-//! that we can store the positions of all rocks in a 200*200 length vector.
-//!
-//! This should be large enough to store all the sand locations we need for
-//! part one. For part two, we need to be able to store, in the worst case
-//! where there ~0 rocks in the cave, a pyramid of sand with height, H.
-//! Such a pyramid will have a base width no greater than 2*H. So a
-//! 500*200 length vector should be more than enough for both parts.
-
-use std::fs::read_to_string;
-
-use crate::grid::Grid;
-use crate::parse;
-
-const X_OFFSET: usize = 250; // Ensure sand source is at centre of grid
-const GRID_WIDTH: usize = 500;
-const GRID_HEIGHT: usize = 200;
-
-
-/// Returns a tuple containing a grid representing the positions of the
-/// rock and an integer representing the location of the bottom of the cave.
-fn parse() -> (Grid<bool>, usize) {
-    let input = read_to_string("data/day14.txt").unwrap();
-    let points = input.lines().map(parse::parse_u32);
-
-    let mut grid = Grid::new(vec![false; GRID_WIDTH * GRID_HEIGHT], GRID_HEIGHT);
-    let mut bottom = 0;
-    for line in points {
-        bottom = bottom.max(*line.iter().skip(1).step_by(2).max().unwrap());
-
-        for window in line.windows(4).step_by(2) {
-            if let &[x0, y0, x1, y1] = window {
-                for col in x1.min(x0)..=x1.max(x0) {
-                    for row in y1.min(y0)..=y1.max(y0) {
-                        grid.set(row as usize, col as usize - X_OFFSET, true);
-                    }
-                }
+//! The rocks and sand are kept in a sparse grid, so the cave can be any size.
+//! The floor in part two is infinitely wide, so rather than being drawn into
+//! the grid, it's checked for whenever a grain tries to fall.
+
+use crate::error::ParseError;
+use crate::grid::{Position, SparseGrid};
+use crate::parse::{integer, normalise};
+use crate::solution::{Solution, Solved};
+
+const SOURCE: Position = (0, 500);
+const AIR: char = '.';
+const ROCK: char = '#';
+const SAND: char = 'o';
+
+/// Returns a (row, col) position from an x,y point
+fn parse_point(input: &str, point: &str) -> Result<Position, ParseError> {
+    let (x, y) = point
+        .split_once(',')
+        .ok_or_else(|| ParseError::at(input, point, "a point such as 498,4"))?;
+
+    Ok((integer(input, y.trim())?, integer(input, x.trim())?))
+}
+
+/// Returns true if a grain of sand can't fall into the given position. With a
+/// `floor`, the floor lies two rows below the bottom.
+fn blocked(cave: &SparseGrid<char>, (row, col): Position, bottom: i64, floor: bool) -> bool {
+    (floor && row == bottom + 2) || cave.get((row, col)) != AIR
+}
+
+/// Fills the cave by dripping sand from the source until either sand starts
+/// falling away to infinity or sand is backed up to the source, and returns
+/// number of grains of sand taken to fill the cave.
+///
+/// Each grain follows the path of the one before until the point that one
+/// came to rest, so rather than dripping every grain from the source, we keep
+/// the path and start the next grain from the end of it.
+fn fill(cave: &mut SparseGrid<char>, bottom: i64, floor: bool) -> u32 {
+    let mut path = vec![SOURCE];
+    let mut count = 0;
+
+    while let Some(&(row, col)) = path.last() {
+        if row > bottom && !floor {
+            break; // Sand falls away to infinity
+        }
+
+        let next = [(row + 1, col), (row + 1, col - 1), (row + 1, col + 1)]
+            .into_iter()
+            .find(|&pos| !blocked(cave, pos, bottom, floor));
+
+        match next {
+            Some(pos) => path.push(pos),
+            None => {
+                cave.set((row, col), SAND);
+                count += 1;
+                path.pop();
             }
         }
     }
 
-    (grid, bottom as usize)
+    count
 }
 
-/// Returns the location a grain of sand comes to rest at after dripping from
-/// the source
-fn drip(grid: &Grid<bool>, bottom: usize) -> Option<(usize, usize)> {
-    let (row0, col0) = (0, 500 - X_OFFSET); // Sand source
-    let (mut row, mut col) = (row0, col0);
-    let mut at_rest = false;
-
-    while !at_rest {
-        if row > bottom {
-            return None; // Part one
-        } else if !grid.get(row + 1, col) {
-            (row, col) = (row + 1, col);
-        } else if !grid.get(row + 1, col - 1) {
-            (row, col) = (row + 1, col - 1);
-        } else if !grid.get(row + 1, col + 1) {
-            (row, col) = (row + 1, col + 1);
-        } else if (row, col) == (row0, col0) {
-            return None; // Part two
-        } else {
-            at_rest = true;
+pub struct Day14;
+
+impl Solution for Day14 {
+    type Input = (SparseGrid<char>, i64);
+    type Output1 = u32;
+    type Output2 = u32;
+
+    /// Returns a tuple containing a grid representing the positions of the
+    /// rock and an integer representing the location of the bottom of the cave.
+    fn parse(input: &str) -> Result<(SparseGrid<char>, i64), ParseError> {
+        let input = &normalise(input);
+
+        let mut cave = SparseGrid::new(AIR);
+        let mut bottom = 0;
+        for line in input.lines() {
+            let path = line
+                .split(" -> ")
+                .map(|point| parse_point(input, point))
+                .collect::<Result<Vec<Position>, ParseError>>()?;
+            bottom = path.iter().map(|(row, _)| *row).fold(bottom, i64::max);
+
+            for window in path.windows(2) {
+                if let &[(r0, c0), (r1, c1)] = window {
+                    for row in r1.min(r0)..=r1.max(r0) {
+                        for col in c1.min(c0)..=c1.max(c0) {
+                            cave.set((row, col), ROCK);
+                        }
+                    }
+                }
+            }
         }
-    }
 
-    Some((row, col))
-}
+        Ok((cave, bottom))
+    }
 
+    /// Returns the number of grains of sand taken to fill the cave
+    fn part1((cave, bottom): &(SparseGrid<char>, i64)) -> Solved<u32> {
+        Ok(fill(&mut cave.clone(), *bottom, false))
+    }
 
-/// Fills the grid by dripping sand from the source until either sand starts
-/// falling away to infinity or sand is backed up to the source, and returns
-/// number of grains of sand taken to fill the grid.
-fn fill(grid: &mut Grid<bool>, bottom: usize) -> u32 {
-    let mut count = 0;
-    while let Some((row, col)) = drip(grid, bottom) {
-        grid.set(row, col, true);
-        count += 1;
+    /// Returns the number of grains of sand taken to fill the cave with a floor
+    fn part2((cave, bottom): &(SparseGrid<char>, i64)) -> Solved<u32> {
+        Ok(fill(&mut cave.clone(), *bottom, true))
     }
-    count
 }
 
-/// Returns the number of grains of sand taken to fill the cave
-pub fn part1() -> u32 {
-    let (mut grid, bottom) = parse();
-    fill(&mut grid, bottom)
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-/// Returns the number of grains of sand taken to fill the cave with a floor
-pub fn part2() -> u32 {
-    let (mut grid, mut bottom) = parse();
+    const EXAMPLE: &str = "\
+498,4 -> 498,6 -> 496,6
+503,4 -> 502,4 -> 502,9 -> 494,9";
 
-    // Add cave floor
-    bottom += 2;
-    for i in 0..grid.ncol {
-        grid.set(bottom, i, true);
+    #[test]
+    fn part1_example() {
+        let input = Day14::parse(EXAMPLE).unwrap();
+        assert_eq!(Day14::part1(&input).unwrap(), 24);
     }
 
-    fill(&mut grid, bottom) + 1
+    #[test]
+    fn part2_example() {
+        let input = Day14::parse(EXAMPLE).unwrap();
+        assert_eq!(Day14::part2(&input).unwrap(), 93);
+    }
 }
-
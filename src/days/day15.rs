@@ -17,18 +17,21 @@
 //! *top-edge* intersections, noting that the empty square must be just above 
 //! such a point.
 
-use std::{fs::read_to_string, collections::HashSet};
+use std::collections::HashSet;
 
-use crate::parse;
+use crate::error::ParseError;
+use crate::parse::{ints_n, normalise};
+use crate::solution::{Solution, Solved};
 
 const HEIGHT: i32 = 2_000_000;
 const DISTRESS_WINDOW: i32 = 4_000_000;
+const TUNING_MULTIPLIER: u64 = 4_000_000;
 
 // Data class ------------------------------------------------------------------
 type Point = (i32, i32);
 
 #[derive(Debug)]
-struct Diamond {
+pub struct Diamond {
     centre: Point,
     radius: i32 // Diamonds are actually circles in taxi-cab space
 }
@@ -65,42 +68,54 @@ impl Diamond {
     }
 }
 
-// Parsing ---------------------------------------------------------------------
+// Solution --------------------------------------------------------------------
+
+pub struct Day15;
 
-/// Returns a tuple containing a vector of sensor diamonds and a vector of
-/// the corresponding beacons
-fn parse() -> (Vec<Diamond>, Vec<Point>) {
-    let input = read_to_string("data/day15.txt").unwrap();
-    let reports = parse::parse_i32(&input);
+impl Solution for Day15 {
+    type Input = (Vec<Diamond>, Vec<Point>);
+    type Output1 = i32;
+    type Output2 = u64;
 
-    let mut diamonds = Vec::new();
-    let mut beacons = Vec::new();
+    /// Returns a tuple containing a vector of sensor diamonds and a vector of
+    /// the corresponding beacons
+    fn parse(input: &str) -> Result<(Vec<Diamond>, Vec<Point>), ParseError> {
+        let input = &normalise(input);
 
-    for report in reports.chunks(4) {
-        if let &[sx, sy, bx, by] = report {
+        let mut diamonds = Vec::new();
+        let mut beacons = Vec::new();
+
+        for line in input.lines() {
+            let [sx, sy, bx, by] = ints_n(input, line)?;
             diamonds.push(Diamond::new((sx, sy), (bx, by)));
             beacons.push((bx, by));
         }
+
+        Ok((diamonds, beacons))
     }
 
-    (diamonds, beacons)
-}
+    /// Returns the number of eliminated positions on the row at `HEIGHT`
+    fn part1((diamonds, beacons): &(Vec<Diamond>, Vec<Point>)) -> Solved<i32> {
+        Ok(eliminated(diamonds, beacons, HEIGHT))
+    }
 
-// Solution --------------------------------------------------------------------
+    /// Returns the tuning frequency of the distress beacon
+    fn part2((diamonds, _): &(Vec<Diamond>, Vec<Point>)) -> Solved<u64> {
+        Ok(tuning_frequency(diamonds, DISTRESS_WINDOW))
+    }
+}
 
-/// Returns the number of eliminated positions on the row at `HEIGHT`
-pub fn part1() -> i32 {
-    let (diamonds, beacons) = parse();
-    
+/// Returns the number of positions on row `y` which can't contain a beacon
+fn eliminated(diamonds: &[Diamond], beacons: &[Point], y: i32) -> i32 {
     let occupied: HashSet<i32> = beacons
         .iter()
-        .filter(|(_, y)| *y == HEIGHT)
+        .filter(|(_, by)| *by == y)
         .map(|(x, _)| *x)
         .collect();
 
     let mut eliminated: Vec<(i32, i32)> = diamonds
         .iter()
-        .filter_map(|o| o.band(HEIGHT))
+        .filter_map(|o| o.band(y))
         .collect();
 
     eliminated.sort_by_key(|(x, _)| *x);
@@ -122,10 +137,9 @@ pub fn part1() -> i32 {
     solution + 1 - (occupied.len() as i32)
 }
 
-
-/// Returns the tuning frequency of the distress beacon
-pub fn part2() -> u64 {
-    let (diamonds, _) = parse();
+/// Returns the tuning frequency of the only position with both coordinates
+/// between 0 and `window` which isn't covered by a sensor
+fn tuning_frequency(diamonds: &[Diamond], window: i32) -> u64 {
     let tops: Vec<Point> = diamonds
         .iter()
         .map(|d| rotate(d.top()))
@@ -136,7 +150,7 @@ pub fn part2() -> u64 {
         for (_, tly) in &tops {
             // Locations where top-left and top-right edges intersect,
             let (x, y) = unrotate((*trx, *tly));
-            if (0..=DISTRESS_WINDOW).contains(&x) & (1..=(DISTRESS_WINDOW+1)).contains(&y) {
+            if (0..=window).contains(&x) & (1..=(window+1)).contains(&y) {
                 // Empty space will be just above the intersection point
                 intersections.push((x, y + 1));  
             }
@@ -147,7 +161,7 @@ pub fn part2() -> u64 {
     // iterate over them to find the one space outside of each diamond.
     intersections.retain(|&pt| diamonds.iter().all(|d| !d.within(pt)));
 
-    (intersections[0].0 as u64) * (DISTRESS_WINDOW as u64) + (intersections[0].1 as u64)
+    (intersections[0].0 as u64) * TUNING_MULTIPLIER + (intersections[0].1 as u64)
 }
 
 fn rotate((x, y): Point) -> Point {
@@ -156,4 +170,39 @@ fn rotate((x, y): Point) -> Point {
 
 fn unrotate((x, y): Point) -> Point {
     ((x+y)/2, (x-y)/2)
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+Sensor at x=2, y=18: closest beacon is at x=-2, y=15
+Sensor at x=9, y=16: closest beacon is at x=10, y=16
+Sensor at x=13, y=2: closest beacon is at x=15, y=3
+Sensor at x=12, y=14: closest beacon is at x=10, y=16
+Sensor at x=10, y=20: closest beacon is at x=10, y=16
+Sensor at x=14, y=17: closest beacon is at x=10, y=16
+Sensor at x=8, y=7: closest beacon is at x=2, y=10
+Sensor at x=2, y=0: closest beacon is at x=2, y=10
+Sensor at x=0, y=11: closest beacon is at x=2, y=10
+Sensor at x=20, y=14: closest beacon is at x=25, y=17
+Sensor at x=17, y=20: closest beacon is at x=21, y=22
+Sensor at x=16, y=7: closest beacon is at x=15, y=3
+Sensor at x=14, y=3: closest beacon is at x=15, y=3
+Sensor at x=20, y=1: closest beacon is at x=15, y=3";
+
+    /// The example asks about row 10, rather than row 2000000
+    #[test]
+    fn part1_example() {
+        let (diamonds, beacons) = Day15::parse(EXAMPLE).unwrap();
+        assert_eq!(eliminated(&diamonds, &beacons, 10), 26);
+    }
+
+    /// The example searches coordinates up to 20, rather than 4000000
+    #[test]
+    fn part2_example() {
+        let (diamonds, _) = Day15::parse(EXAMPLE).unwrap();
+        assert_eq!(tuning_frequency(&diamonds, 20), 56000011);
+    }
+}
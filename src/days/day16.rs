@@ -2,12 +2,15 @@
 //!
 //! 
 
+use crate::error::ParseError;
 use crate::grid::Grid;
-use crate::parse;
-use std::{cmp::Reverse, collections::HashMap, fs::read_to_string};
+use crate::parse::{normalise, Pattern};
+use crate::search;
+use crate::solution::{Solution, Solved};
+use std::{cmp::Reverse, collections::HashMap};
 
 // Parsing --------------------------------------------------------------------
-struct Cave {
+pub struct Cave {
     valves: Vec<Valve>,
     proximity: Grid<u32>
 }
@@ -18,161 +21,153 @@ struct Valve {
     tunnels: Vec<String>,
 }
 
-impl Valve {
-    fn parse(line: &str) -> Self {
-        let node_chars = line
-            .chars()
-            .skip(1)
-            .filter(|x| x.is_ascii_uppercase())
-            .collect::<Vec<char>>();
-
-        let mut nodes = node_chars.chunks(2).map(|x| x.iter().collect::<String>());
-
-        let name = nodes.next().unwrap();
-        let tunnels: Vec<String> = nodes.collect();
+const VALVE: &str = "Valve {name} has flow rate={rate}; {tunnels}";
 
-        let rate: u32 = parse::parse_u32(line)[0];
+impl Valve {
+    /// Parses a line like 'Valve AA has flow rate=0; tunnels lead to valves DD, II'
+    /// with the `VALVE` pattern
+    fn parse(input: &str, pattern: &Pattern, line: &str) -> Result<Self, ParseError> {
+        let fields = pattern.parse(input, line)?;
+        let (name, rate, rest) = (fields.slice("name"), fields.get("rate")?, fields.slice("tunnels"));
+
+        let tunnels = rest
+            .strip_prefix("tunnels lead to valves ")
+            .or_else(|| rest.strip_prefix("tunnel leads to valve "))
+            .ok_or_else(|| ParseError::at(input, rest, "a list of tunnels"))?;
+
+        let mut valves = vec![name];
+        valves.extend(tunnels.split(", "));
+        if let Some(v) = valves.iter().find(|v| !is_valve_name(v)) {
+            return Err(ParseError::at(input, v, "a two letter valve name"));
+        }
 
-        Valve {
-            name,
+        Ok(Valve {
+            name: name.to_string(),
             rate,
-            tunnels,
-        }
+            tunnels: tunnels.split(", ").map(String::from).collect(),
+        })
     }
 }
 
-/// Returns a vector of Valves and a Grid containing their proximities to one another
-fn parse() -> Cave {
-    let input = read_to_string("data/day16.txt").unwrap();
+/// Returns true if the string is two uppercase letters
+fn is_valve_name(name: &str) -> bool {
+    name.len() == 2 && name.chars().all(|c| c.is_ascii_uppercase())
+}
 
-    // Read all valves from input
-    let mut valves: Vec<Valve> = input.lines().map(Valve::parse).collect();
+// Solutions ------------------------------------------------------------------
 
-    // Create a full graph
-    let mut graph = HashMap::new();
-    for v in &valves {
-        graph.insert(v.name.clone(), v.tunnels.clone());
-    }
+pub struct Day16;
 
-    // Now strip out the zero rate valves
-    valves.retain(|v| v.rate > 0 || v.name == "AA");
-    
-    // Sort valves by rate, descending order
-    valves.sort_by_key(|v| Reverse(v.rate));
-
-    // ... and use the graph to find min distances between remaining valves
-    let mut proximity = Grid::new(vec![u32::MAX; valves.len() * valves.len()], valves.len());
-    for (i, a) in valves.iter().enumerate() {
-        for (j, b) in valves.iter().enumerate() {
-            if proximity.get(i, j) < u32::MAX {
-                continue;
-            }
+impl Solution for Day16 {
+    type Input = Cave;
+    type Output1 = u32;
+    type Output2 = u32;
 
-            // Proximity matrix should be symmetric
-            let dist = bfs(&graph, &a.name, &b.name);
-            proximity.set(i, j, dist);
-            proximity.set(j, i, dist);
-        }
-    }
+    /// Returns a vector of Valves and a Grid containing their proximities to one another
+    fn parse(input: &str) -> Result<Cave, ParseError> {
+        let input = &normalise(input);
 
-    Cave { valves, proximity }
-}
+        // Read all valves from input
+        let pattern = Pattern::new(VALVE);
+        let mut valves = input
+            .lines()
+            .map(|l| Valve::parse(input, &pattern, l))
+            .collect::<Result<Vec<Valve>, ParseError>>()?;
 
-/// Return shortest path through the graph from start to end, computed via BFS
-fn bfs(graph: &HashMap<String, Vec<String>>, start: &str, end: &str) -> u32 {
-    let mut queue = vec![start];
-    let mut visited = Vec::new();
-    let mut next: Vec<&str> = Vec::new();
-
-    let mut dist = 0;
-    loop {
-        while let Some(node) = queue.pop() {
-            if node == end {
-                return dist;
-            } else if visited.contains(&node) {
-                continue;
-            }
+        // Create a full graph
+        let mut graph = HashMap::new();
+        for v in &valves {
+            graph.insert(v.name.clone(), v.tunnels.clone());
+        }
 
-            visited.push(node);
-            graph.get(node).unwrap().iter().for_each(|v| next.push(v));
+        // Now strip out the zero rate valves
+        valves.retain(|v| v.rate > 0 || v.name == "AA");
+        
+        // Sort valves by rate, descending order
+        valves.sort_by_key(|v| Reverse(v.rate));
+
+        // ... and use the graph to find min distances between remaining valves
+        let mut proximity = Grid::new(vec![u32::MAX; valves.len() * valves.len()], valves.len());
+        for (i, a) in valves.iter().enumerate() {
+            let paths = search::bfs(a.name.as_str(), search::graph_steps(&graph), |_| false);
+            for (j, b) in valves.iter().enumerate() {
+                if let Some(dist) = paths.cost(&b.name.as_str()) {
+                    proximity.set(i, j, dist as u32);
+                }
+            }
         }
 
-        queue.append(&mut next);
-        dist += 1;
+        Ok(Cave { valves, proximity })
     }
-}
 
-// Solutions ------------------------------------------------------------------
-
-/// Returns maximal pressure release, computed via branch and bound search
-pub fn part1() -> u32 {
-    let cave = parse();
-    let loc = cave.valves.len() - 1;
-
-    // Use a bit set to represent the open valves
-    let still_open = (0..loc).fold(0, |set, i| set | 1 << i);
-
-    // Define the initial state to pass to the branch and bound algorithm
-    let state = State {
-        loc,
-        still_open,
-        time: 30,
-        score: 0,
-    };
-
-    // We'll prune the graph by getting rid of branches whose upper bound
-    // scores are lower than the cutoff. This needs to be a closure like this
-    // so that we can reuse `branch_and_bound` for part two
-    let mut best = 0;
-    let mut cutoff = |_, score: u32| {
-        best = best.max(score);
-        best
-    };
-    branch_and_bound(&state, &cave, &mut cutoff);
-
-    best
-}
-
-pub fn part2() -> u32 {
-    let cave = parse();
-    let loc = cave.valves.len() - 1;
-
-    // Use a bit set to represent the open valves
-    let still_open = (0..loc).fold(0, |set, i| set | 1 << i);
+    /// Returns maximal pressure release, computed via branch and bound search
+    fn part1(cave: &Cave) -> Solved<u32> {
+        let loc = cave.valves.len() - 1;
+
+        // Use a bit set to represent the open valves
+        let still_open = (0..loc).fold(0, |set, i| set | 1 << i);
+
+        // Define the initial state to pass to the branch and bound algorithm
+        let state = State {
+            loc,
+            still_open,
+            time: 30,
+            score: 0,
+        };
+
+        // We'll prune the graph by getting rid of branches whose upper bound
+        // scores are lower than the cutoff. This needs to be a closure like this
+        // so that we can reuse `branch_and_bound` for part two
+        let mut best = 0;
+        let mut cutoff = |_, score: u32| {
+            best = best.max(score);
+            best
+        };
+        branch_and_bound(&state, cave, &mut cutoff);
+
+        Ok(best)
+    }
 
-    // Define the initial state to pass to the branch and bound algorithm
-    let state = State {
-        loc,
-        still_open,
-        time: 26,
-        score: 0,
-    };
-
-    // We want to explore the entire search tree without pruning anything, and
-    // store all the results. This gives us high-scores for all possible
-    // subsets
-    let mut cache = vec![0; still_open + 1];
-    let mut cutoff = |still_open: usize, score: u32| {
-        cache[still_open] = cache[still_open].max(score);
-        0
-    };
-    branch_and_bound(&state, &cave, &mut cutoff);
-
-    // We can now iterate over all the disjoint subsets, and take the max score
-    let mut best = 0;
-    let scores: Vec<_> = cache.iter().enumerate().filter(|(_, &s)| s > 0).collect();
-    for i in 0..scores.len() {
-        let (m1, &s1) = scores[i];
-        for (m2, &s2) in scores.iter() {
-            // m1 and m2 denote the valves _not_ in the set. We get the valves
-            // in each set by xor'ing against the full set of valves.
-            if (still_open ^ m1) & (still_open ^ m2) == 0 {
-                best = best.max(s1+s2);
+    fn part2(cave: &Cave) -> Solved<u32> {
+        let loc = cave.valves.len() - 1;
+
+        // Use a bit set to represent the open valves
+        let still_open = (0..loc).fold(0, |set, i| set | 1 << i);
+
+        // Define the initial state to pass to the branch and bound algorithm
+        let state = State {
+            loc,
+            still_open,
+            time: 26,
+            score: 0,
+        };
+
+        // We want to explore the entire search tree without pruning anything, and
+        // store all the results. This gives us high-scores for all possible
+        // subsets
+        let mut cache = vec![0; still_open + 1];
+        let mut cutoff = |still_open: usize, score: u32| {
+            cache[still_open] = cache[still_open].max(score);
+            0
+        };
+        branch_and_bound(&state, cave, &mut cutoff);
+
+        // We can now iterate over all the disjoint subsets, and take the max score
+        let mut best = 0;
+        let scores: Vec<_> = cache.iter().enumerate().filter(|(_, &s)| s > 0).collect();
+        for i in 0..scores.len() {
+            let (m1, &s1) = scores[i];
+            for (m2, &s2) in scores.iter() {
+                // m1 and m2 denote the valves _not_ in the set. We get the valves
+                // in each set by xor'ing against the full set of valves.
+                if (still_open ^ m1) & (still_open ^ m2) == 0 {
+                    best = best.max(s1+s2);
+                }
             }
         }
-    }
 
-    best
+        Ok(best)
+    }
 }
 
 // Helpers --------------------------------------------------------------------
@@ -186,7 +181,7 @@ struct State {
 
 /// Return the next state after moving to the valve with index k
 fn open(loc: usize, state: &State, cave: &Cave) -> Option<State> {
-    let d = cave.proximity.get(state.loc, loc) + 1;
+    let d = cave.proximity.get(state.loc, loc).saturating_add(1); // Unreachable valves are MAX
     if d > state.time {
         return None;
     }
@@ -235,3 +230,32 @@ fn branch_and_bound(state: &State, cave: &Cave, cutoff: &mut impl FnMut(usize, u
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+Valve AA has flow rate=0; tunnels lead to valves DD, II, BB
+Valve BB has flow rate=13; tunnels lead to valves CC, AA
+Valve CC has flow rate=2; tunnels lead to valves DD, BB
+Valve DD has flow rate=20; tunnels lead to valves CC, AA, EE
+Valve EE has flow rate=3; tunnels lead to valves FF, DD
+Valve FF has flow rate=0; tunnels lead to valves EE, GG
+Valve GG has flow rate=0; tunnels lead to valves FF, HH
+Valve HH has flow rate=22; tunnel leads to valve GG
+Valve II has flow rate=0; tunnels lead to valves AA, JJ
+Valve JJ has flow rate=21; tunnel leads to valve II";
+
+    #[test]
+    fn part1_example() {
+        let input = Day16::parse(EXAMPLE).unwrap();
+        assert_eq!(Day16::part1(&input).unwrap(), 1651);
+    }
+
+    #[test]
+    fn part2_example() {
+        let input = Day16::parse(EXAMPLE).unwrap();
+        assert_eq!(Day16::part2(&input).unwrap(), 1707);
+    }
+}
@@ -8,18 +8,21 @@
 //! the rock bits with the bits of the context it's moving into.
 //!
 //! In theory the second part is challenging because you've got to keep track
-//! of the state of the chamber. However, in practice, it turns out that the
-//! state of the chamber doesn't matter! All we care about is finding out
-//! when the rock/jet cycle begins to loop. So we initialise an RxJ grid, where
-//! R is the length of the rock cycle and J is the length of the jet cycle. We 
-//! use the grid to keep track of the step on which each rock/jet combo was
-//! observed and the height of the tower at that time. If we observe a combo
-//! re-appear, then we've hit a cycle, so we can fast-forward.
-
+//! of the state of the chamber. In practice only the top few rows matter,
+//! since rocks rarely fall any further than that. So, as each rock spawns, we
+//! record the step and tower height against the rock, the next jet and the
+//! top of the chamber. If we observe the same combination re-appear, then
+//! we've hit a cycle, so we can fast-forward.
+//!
+//! Matching on the rock and jet alone is enough for real inputs, but not for
+//! the example, whose short jet pattern repeats before the tower settles.
 
+use std::collections::HashMap;
 use std::iter::zip;
-// use std::collections::hash_map::Entry;
-use crate::grid::Grid;
+
+use crate::error::ParseError;
+use crate::parse::normalise;
+use crate::solution::{Solution, Solved};
 
 // Constants -------------------------------------------------------------------
 
@@ -31,6 +34,7 @@ struct Rock {
 }
 
 const FLOOR: u8 = 0b11111111;
+const SURFACE_DEPTH: usize = 16; // Rows of the chamber compared between cycles
 const EMPTY: u8 = 0b00000001; // Chamber is only 7 spaces wide
 
 const ROCKS: [Rock; 5] = [
@@ -78,25 +82,11 @@ const ROCKS: [Rock; 5] = [
 // Solution --------------------------------------------------------------------
 
 #[derive(Debug)]
-enum Direction {
+pub enum Direction {
     Left,
     Right
 }
 
-/// Returns the input as a vector of Directions
-fn parse() -> Vec<Direction> {
-    include_bytes!("../../data/day17.txt").iter().
-        map(|b| {
-            match b {
-                b'<' => Direction::Left,
-                b'>' => Direction::Right,
-                _ => unreachable!()
-            }
-        })
-        .collect()
-}
-
-
 impl Rock {
     /// Pushes the rock in a Direction if it's not blocked
     fn push(&mut self, dir: &Direction, context: &[u8]) {
@@ -139,12 +129,12 @@ fn overlap(x: &[u8], y: &[u8]) -> bool {
 
 /// Simulates the rocks falling, subject to the jets of air, across a given 
 /// number of iterations
-fn simulate(input: Vec<Direction>, iterations: usize) -> usize {
-    let mut jets = input.iter().enumerate().cycle();
+fn simulate(input: &[Direction], iterations: usize) -> usize {
+    let mut jets = input.iter().enumerate().cycle().peekable();
     let mut rocks = ROCKS.iter().enumerate().cycle();
-    
-    let mut cache_step = Grid::new(vec![0; input.len() * ROCKS.len()], ROCKS.len());
-    let mut cache_height = Grid::new(vec![0; input.len() * ROCKS.len()], ROCKS.len());
+
+    // (rock, jet, surface) -> (step, tower height)
+    let mut cache: HashMap<(usize, usize, u128), (usize, usize)> = HashMap::new();
 
     let mut chamber: Vec<u8> = vec![FLOOR];
     let mut tower_height = 0;
@@ -159,26 +149,25 @@ fn simulate(input: Vec<Direction>, iterations: usize) -> usize {
         let (r, Rock {occupying, height})= rocks.next().unwrap();
         let mut rock = Rock { occupying: *occupying, height: *height};
         let mut rock_pos = tower_height+4;
+
+        // Check cache and fastforward
+        let (j, _) = *jets.peek().unwrap();
+        let key = (r, j, surface(&chamber, tower_height));
+        if let Some(&(i0, h0)) = cache.get(&key) {
+            let remaining = iterations - i + 1;
+            let cycle_length = i - i0;
+            let (d, m) = (remaining / cycle_length, remaining % cycle_length);
+            if m == 0 {
+                return tower_height + (tower_height - h0)*d;
+            }
+        } else {
+            cache.insert(key, (i, tower_height));
+        }
         
         // Drop rock
         let mut context = &chamber[rock_pos..(rock_pos+4)];
         loop {
-            let (j, jet) = jets.next().unwrap();
-
-            // Check cache and fastforward
-            let i0 = cache_step.get(r, j);
-            if i0 > 0 {
-                let h0 = cache_height.get(r, j);
-                let remaining = iterations - i;
-                let cycle_length = i-i0;
-                let (d, m) = (remaining / cycle_length, remaining % cycle_length);
-                if m == 0 {
-                    return tower_height + (tower_height - h0)*d;
-                }
-            } else {
-                cache_step.set(r, j, i);
-                cache_height.set(r, j, tower_height);
-            }
+            let (_, jet) = jets.next().unwrap();
 
             // Move rock
             rock.push(jet, context);
@@ -200,12 +189,73 @@ fn simulate(input: Vec<Direction>, iterations: usize) -> usize {
     tower_height
 }
 
-pub fn part1() -> usize {
-    let input = parse();
-    simulate(input, 2022)
+/// Returns the top rows of the tower packed into a single integer, with
+/// floor underneath if the tower isn't that tall yet
+fn surface(chamber: &[u8], tower_height: usize) -> u128 {
+    (0..SURFACE_DEPTH).fold(0, |acc, k| {
+        let row = tower_height.checked_sub(k).map_or(FLOOR, |y| chamber[y]);
+        (acc << 8) | row as u128
+    })
 }
 
-pub fn part2() -> usize {
-    let input = parse();
-    simulate(input, 1_000_000_000_000)
+pub struct Day17;
+
+impl Solution for Day17 {
+    type Input = Vec<Direction>;
+    type Output1 = usize;
+    type Output2 = usize;
+
+    /// Returns the input as a vector of Directions
+    fn parse(input: &str) -> Result<Vec<Direction>, ParseError> {
+        let input = &normalise(input);
+
+        let jets: Vec<Direction> = input.as_bytes().iter().enumerate().
+            map(|(i, b)| {
+                match b {
+                    b'<' => Ok(Direction::Left),
+                    b'>' => Ok(Direction::Right),
+                    _ => Err(ParseError::at_byte(input, i, "`<` or `>`"))
+                }
+            })
+            .collect::<Result<_, _>>()?;
+
+        if jets.is_empty() {
+            return Err(ParseError::eof(input, "`<` or `>`"));
+        }
+
+        Ok(jets)
+    }
+
+    fn part1(input: &Vec<Direction>) -> Solved<usize> {
+        Ok(simulate(input, 2022))
+    }
+
+    fn part2(input: &Vec<Direction>) -> Solved<usize> {
+        Ok(simulate(input, 1_000_000_000_000))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+>>><<><>><<<>><>>><<<>>><<<><<<>><>><<>>";
+
+    #[test]
+    fn part1_example() {
+        let input = Day17::parse(EXAMPLE).unwrap();
+        assert_eq!(Day17::part1(&input).unwrap(), 3068);
+    }
+
+    #[test]
+    fn part2_example() {
+        let input = Day17::parse(EXAMPLE).unwrap();
+        assert_eq!(Day17::part2(&input).unwrap(), 1514285714288);
+    }
+
+    #[test]
+    fn no_jets() {
+        assert_eq!(Day17::parse("").unwrap_err().expected, "`<` or `>`");
+    }
 }
@@ -1,131 +1,119 @@
 //! Counting cubes
+//!
+//! The droplet is drawn into a 3D grid with a layer of air all the way
+//! around it, so that a flood fill from a corner reaches all of the air
+//! outside the droplet. The exterior surface is then the faces of the lava
+//! cubes which touch that air.
 
-use std::fs::read_to_string;
+use crate::error::ParseError;
+use crate::grid::{Connectivity, GridN};
+use crate::parse::{integer, normalise};
+use crate::solution::{Solution, Solved};
 
-// 3D grid ---------------------------------------------------------------------
+type Coord = [usize; 3];
 
-type Coord = (usize, usize, usize);
-struct Grid3d {
-    elements: Vec<usize>,
-    dim: (usize, usize, usize),
-}
+const AIR: u8 = 0;
+const LAVA: u8 = 1;
+const STEAM: u8 = 2;
 
-impl Grid3d {
-    /// Returns the element at location (i, j, k) if it exists
-    fn get(&self, coord: Coord) -> Option<&usize> {
-        self.elements.get(self._coord_to_idx(coord))
-    }
+// Parse -----------------------------------------------------------------------
 
-    /// Set the value of the element at location (i, j, k).
-    fn set(&mut self, coord: Coord, x: usize) {
-        let idx = self._coord_to_idx(coord);
-        self.elements[idx] = x;
-    }
+fn parse_coord(input: &str, s: &str) -> Result<Coord, ParseError> {
+    let triplet = s
+        .split_terminator(',')
+        .map(|x| integer(input, x))
+        .collect::<Result<Vec<usize>, ParseError>>()?;
 
-    /// Convert i,j,k coord to vector index
-    fn _coord_to_idx(&self, (i, j, k): Coord) -> usize {
-        (i * self.dim.1 * self.dim.2) + (j * self.dim.2) + k
+    match triplet[..] {
+        [x, y, z] => Ok([x, y, z]),
+        _ => Err(ParseError::at(input, s, "three comma separated coordinates")),
     }
 }
 
-// Parse -----------------------------------------------------------------------
+pub struct Day18;
 
-fn parse_coord(s: &str) -> Coord {
-    let triplet: Vec<usize> = s
-        .split_terminator(',')
-        .map(|x| x.parse::<usize>().unwrap())
-        .collect();
-    (triplet[0], triplet[1], triplet[2])
-}
+impl Solution for Day18 {
+    type Input = (GridN<u8, 3>, Vec<Coord>);
+    type Output1 = usize;
+    type Output2 = usize;
 
-/// Return the grid and the coordinates of each droplet cube in the grid
-fn parse() -> (Grid3d, Vec<Coord>) {
-    let input = read_to_string("data/day18.txt").unwrap();
-    let cubes: Vec<Coord> = input.lines().map(parse_coord).collect();
-
-    // Collect grid dimensions
-    let x = cubes.iter().map(|pt| pt.0).max().unwrap();
-    let y = cubes.iter().map(|pt| pt.1).max().unwrap();
-    let z = cubes.iter().map(|pt| pt.2).max().unwrap();
-
-    // Put droplet cubes into the grid, leaving some space around the outside
-    let elements = vec![0; (x + 3) * (y + 3) * (z + 3)];
-    let mut grid = Grid3d {
-        elements,
-        dim: (x + 3, y + 3, z + 3),
-    };
-
-    // The lowest index in the input is 0, but we want to leave some space
-    for (x, y, z) in &cubes {
-        grid.set((*x + 1, *y + 1, *z + 1), 1);
-    }
+    /// Return the grid and the coordinates of each droplet cube in the grid
+    fn parse(input: &str) -> Result<(GridN<u8, 3>, Vec<Coord>), ParseError> {
+        let input = &normalise(input);
 
-    (grid, cubes)
-}
+        let cubes = input
+            .lines()
+            .map(|l| parse_coord(input, l))
+            .collect::<Result<Vec<Coord>, ParseError>>()?;
 
-pub fn part1() -> usize {
-    let (grid, cubes) = parse();
-
-    // Ignoring surrounding space, count all the neighbours
-    let mut neighbours = 0;
-    for i in 1..(grid.dim.0 - 1) {
-        for j in 1..(grid.dim.1 - 1) {
-            for k in 1..(grid.dim.2 - 1) {
-                if grid.get((i, j, k)) == Some(&0) {
-                    continue;
-                }
-                neighbours += sum_neighbours(&grid, (i, j, k))
-            }
+        if cubes.is_empty() {
+            return Err(ParseError::eof(input, "at least one cube"));
         }
-    }
 
-    (cubes.len() * 6) - neighbours
-}
+        // The lowest index in the input is 0, but we want to leave some space
+        // around the outside, so every cube is shifted by one
+        let cubes: Vec<Coord> = cubes.iter().map(|c| c.map(|x| x + 1)).collect();
+        let dims = [0, 1, 2].map(|k| cubes.iter().map(|c| c[k]).max().unwrap() + 2);
 
-pub fn part2() -> usize {
-    let (mut grid, _) = parse();
-
-    // First 'colour' the external cubes
-    flood_fill(&mut grid, (0, 0, 0), 2);
-
-    // Next we want to count the neighbours of each *internal* cube
-    // Since internal cubes can only neighbour droplet cubes, we can just
-    // sum the neighbours.
-    let mut neighbours = 0;
-    for i in 1..(grid.dim.0 - 1) {
-        for j in 1..(grid.dim.1 - 1) {
-            for k in 1..(grid.dim.2 - 1) {
-                if grid.get((i, j, k)) == Some(&0) {
-                    neighbours += sum_neighbours(&grid, (i, j, k))
-                }
-            }
+        let mut grid = GridN::new(dims, AIR);
+        for &cube in &cubes {
+            grid.set(cube, LAVA);
         }
+
+        Ok((grid, cubes))
+    }
+
+    /// Returns the number of cube faces which don't touch another cube
+    fn part1((grid, cubes): &(GridN<u8, 3>, Vec<Coord>)) -> Solved<usize> {
+        Ok(surface(grid, cubes, AIR))
     }
 
-    part1() - neighbours
+    /// Returns the number of cube faces which touch the air outside
+    fn part2((grid, cubes): &(GridN<u8, 3>, Vec<Coord>)) -> Solved<usize> {
+        let mut grid = grid.clone();
+        grid.flood_fill([0, 0, 0], Connectivity::Face, STEAM);
+
+        Ok(surface(&grid, cubes, STEAM))
+    }
 }
 
-/// Take the sum of the elements neighbouring a given co-ord
-fn sum_neighbours(grid: &Grid3d, coord: Coord) -> usize {
-    let (i, j, k) = coord;
-    grid.get((i + 1, j, k)).unwrap()
-        + grid.get((i - 1, j, k)).unwrap()
-        + grid.get((i, j + 1, k)).unwrap()
-        + grid.get((i, j - 1, k)).unwrap()
-        + grid.get((i, j, k + 1)).unwrap()
-        + grid.get((i, j, k - 1)).unwrap()
+/// Returns the number of cube faces which touch a cell holding `outside`
+fn surface(grid: &GridN<u8, 3>, cubes: &[Coord], outside: u8) -> usize {
+    cubes
+        .iter()
+        .flat_map(|&cube| grid.neighbours(cube, Connectivity::Face))
+        .filter(|&c| grid.get(c) == outside)
+        .count()
 }
 
-/// Flood the grid with a given value starting from a given position
-fn flood_fill(grid: &mut Grid3d, start: Coord, with: usize) {
-    if grid.get(start) == Some(&0) {
-        grid.set(start, with);
-        let (x0, y0, z0) = start;
-        flood_fill(grid, (x0 + 1, y0, z0), with);
-        flood_fill(grid, (x0.saturating_sub(1), y0, z0), with);
-        flood_fill(grid, (x0, y0 + 1, z0), with);
-        flood_fill(grid, (x0, y0.saturating_sub(1), z0), with);
-        flood_fill(grid, (x0, y0, z0 + 1), with);
-        flood_fill(grid, (x0, y0, z0.saturating_sub(1)), with);
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+2,2,2
+1,2,2
+3,2,2
+2,1,2
+2,3,2
+2,2,1
+2,2,3
+2,2,4
+2,2,6
+1,2,5
+3,2,5
+2,1,5
+2,3,5";
+
+    #[test]
+    fn part1_example() {
+        let input = Day18::parse(EXAMPLE).unwrap();
+        assert_eq!(Day18::part1(&input).unwrap(), 64);
+    }
+
+    #[test]
+    fn part2_example() {
+        let input = Day18::parse(EXAMPLE).unwrap();
+        assert_eq!(Day18::part2(&input).unwrap(), 58);
     }
 }
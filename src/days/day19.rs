@@ -5,20 +5,20 @@
 //!
 //! - We cannot possibly do better than we could if we were ignoring ore.
 //! - We cannot possibly have more clay than T+2(T-1)+3(T-2)+... less the amount
-//! we have spent on obsidian robots. So we'll just assume we always have such
-//! an amount
+//!   we have spent on obsidian robots. So we'll just assume we always have such
+//!   an amount
 //! - And so, given we're assuming maximal clay balance, we cannot possibly get
-//! more obsidian than we could by building obsidian bots whenever we can.
+//!   more obsidian than we could by building obsidian bots whenever we can.
 //! - And so, given we're assuming maximal obsidian balance, we cannot possibly
-//! get more geodes than we could by building geode bots whenever we can.
+//!   get more geodes than we could by building geode bots whenever we can.
 //!
 //! We also don't need to mine more resources in a given turn than we can spend
 //! in a given turn, so we can prune branches where the number of robots of
 //! a given type exceeds the amount of that resource we can spend in one turn.
 
-use std::fs::read_to_string;
-
-use crate::parse::parse_u32;
+use crate::parse::{ints_n, normalise};
+use crate::error::ParseError;
+use crate::solution::{Solution, Solved};
 
 // Data classes ----------------------------------------------------------------
 type Minerals = [u8; 4];
@@ -49,26 +49,25 @@ struct State {
     countdown: u8,
 }
 
-struct Blueprint {
+pub struct Blueprint {
     costs: [Minerals; 4],
     limits: Minerals, // The most we'll spend of each mineral in a given minute
 }
 
 impl Blueprint {
-    fn parse(s: &str) -> Self {
-        let integers = parse_u32(s);
-        let mut ints = integers.iter();
+    fn parse(input: &str, s: &str) -> Result<Self, ParseError> {
+        // The first integer is the blueprint's index
+        let [_, ore, clay, obsidian_ore, obsidian_clay, geode_ore, geode_obsidian] = ints_n(input, s)?;
 
         let mut costs: [Minerals; 4] = [[0; 4]; 4];
-        ints.next(); // First element is blueprint index
-        costs[0][0] = *ints.next().unwrap() as u8;
-        costs[1][0] = *ints.next().unwrap() as u8;
-        costs[2][0] = *ints.next().unwrap() as u8;
-        costs[2][1] = *ints.next().unwrap() as u8;
-        costs[3][0] = *ints.next().unwrap() as u8;
-        costs[3][2] = *ints.next().unwrap() as u8;
-
-        Blueprint::new(costs)
+        costs[0][0] = ore;
+        costs[1][0] = clay;
+        costs[2][0] = obsidian_ore;
+        costs[2][1] = obsidian_clay;
+        costs[3][0] = geode_ore;
+        costs[3][2] = geode_obsidian;
+
+        Ok(Blueprint::new(costs))
     }
 
     fn new(costs: [Minerals; 4]) -> Self {
@@ -84,40 +83,44 @@ impl Blueprint {
 
 // Solution --------------------------------------------------------------------
 
-fn parse() -> Vec<Blueprint> {
-    let input = read_to_string("data/day19.txt").unwrap();
-    input.lines().map(Blueprint::parse).collect()
-}
+pub struct Day19;
 
-pub fn part1() -> usize {
-    let blueprints = parse();
-    let scores = blueprints.iter().map(|blueprint| {
-        let state = State {
-            balance: [0; 4],
-            robots: [1, 0, 0, 0],
-            countdown: 24,
-        };
-        branch_and_bound(state, blueprint, 0)
-    });
-
-    scores
-        .enumerate()
-        .map(|(i, s)| (i + 1) * (s as usize))
-        .sum()
-}
+impl Solution for Day19 {
+    type Input = Vec<Blueprint>;
+    type Output1 = usize;
+    type Output2 = usize;
+
+    fn parse(input: &str) -> Result<Vec<Blueprint>, ParseError> {
+        let input = &normalise(input);
+
+        input.lines().map(|l| Blueprint::parse(input, l)).collect()
+    }
+
+    fn part1(blueprints: &Vec<Blueprint>) -> Solved<usize> {
+        let scores = blueprints.iter().map(|blueprint| {
+            let state = State {
+                balance: [0; 4],
+                robots: [1, 0, 0, 0],
+                countdown: 24,
+            };
+            branch_and_bound(state, blueprint, 0)
+        });
+
+        Ok(scores.enumerate().map(|(i, s)| (i + 1) * (s as usize)).sum())
+    }
 
-pub fn part2() -> usize {
-    let blueprints = parse();
-    let scores = blueprints.iter().take(3).map(|blueprint| {
-        let state = State {
-            balance: [0; 4],
-            robots: [1, 0, 0, 0],
-            countdown: 32,
-        };
-        branch_and_bound(state, blueprint, 0)
-    });
-
-    scores.map(|i| i as usize).product()
+    fn part2(blueprints: &Vec<Blueprint>) -> Solved<usize> {
+        let scores = blueprints.iter().take(3).map(|blueprint| {
+            let state = State {
+                balance: [0; 4],
+                robots: [1, 0, 0, 0],
+                countdown: 32,
+            };
+            branch_and_bound(state, blueprint, 0)
+        });
+
+        Ok(scores.map(|i| i as usize).product())
+    }
 }
 
 fn branch_and_bound(state: State, blueprint: &Blueprint, best: u8) -> u8 {
@@ -199,3 +202,24 @@ fn upper_bound(state: State, blueprint: &Blueprint) -> u8 {
 
     balance[3]
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+Blueprint 1: Each ore robot costs 4 ore. Each clay robot costs 2 ore. Each obsidian robot costs 3 ore and 14 clay. Each geode robot costs 2 ore and 7 obsidian.
+Blueprint 2: Each ore robot costs 2 ore. Each clay robot costs 3 ore. Each obsidian robot costs 3 ore and 8 clay. Each geode robot costs 3 ore and 12 obsidian.";
+
+    #[test]
+    fn part1_example() {
+        let input = Day19::parse(EXAMPLE).unwrap();
+        assert_eq!(Day19::part1(&input).unwrap(), 33);
+    }
+
+    #[test]
+    fn part2_example() {
+        let input = Day19::parse(EXAMPLE).unwrap();
+        assert_eq!(Day19::part2(&input).unwrap(), 3472);
+    }
+}
@@ -18,33 +18,55 @@
 //!
 //! TODO: Try using a tree for part two.
 
-use std::fs::read_to_string;
+use crate::error::ParseError;
+use crate::parse::{integer, normalise};
+use crate::solution::{Solution, Solved};
 
 const GROVE_DELTAS: [usize; 3] = [1000, 2000, 3000];
 const DECRYPTION_KEY: i64 = 811589153;
 
-fn parse() -> Vec<i64> {
-    let input = read_to_string("data/day20.txt").unwrap();
-    input.lines().map(|s| s.parse::<i64>().unwrap()).collect()
-}
+pub struct Day20;
 
-pub fn part1() -> i64 {
-    let mut list = parse();
-    mix_once(&mut list);
-    grove_coordinates(&list)
-}
+impl Solution for Day20 {
+    type Input = Vec<i64>;
+    type Output1 = i64;
+    type Output2 = i64;
 
-pub fn part2() -> i64 {
-    let mut list: Vec<(usize, i64)> = parse()
-        .iter()
-        .map(|i| *i * DECRYPTION_KEY)
-        .enumerate()
-        .collect();
+    fn parse(input: &str) -> Result<Vec<i64>, ParseError> {
+        let input = &normalise(input);
+
+        let list: Vec<i64> = input.lines().map(|s| integer(input, s)).collect::<Result<_, _>>()?;
+
+        // Grove coordinates are counted from the 0, and mixing moves numbers
+        // around the other n - 1
+        if list.len() < 2 {
+            return Err(ParseError::eof(input, "at least two numbers"));
+        }
+        if !list.contains(&0) {
+            return Err(ParseError::eof(input, "a 0 in the list"));
+        }
+
+        Ok(list)
+    }
+
+    fn part1(list: &Vec<i64>) -> Solved<i64> {
+        let mut list = list.clone();
+        mix_once(&mut list);
+        Ok(grove_coordinates(&list))
+    }
+
+    fn part2(list: &Vec<i64>) -> Solved<i64> {
+        let mut list: Vec<(usize, i64)> = list
+            .iter()
+            .map(|i| *i * DECRYPTION_KEY)
+            .enumerate()
+            .collect();
 
-    mix(&mut list, 10);
+        mix(&mut list, 10);
 
-    let list_bare: Vec<i64> = list.iter().map(|(_, x)| x).cloned().collect();
-    grove_coordinates(&list_bare)
+        let list_bare: Vec<i64> = list.iter().map(|(_, x)| x).cloned().collect();
+        Ok(grove_coordinates(&list_bare))
+    }
 }
 
 fn grove_coordinates(list: &[i64]) -> i64 {
@@ -56,7 +78,7 @@ fn grove_coordinates(list: &[i64]) -> i64 {
 }
 
 /// Mix the provided list a given number of times
-fn mix(list: &mut Vec<(usize, i64)>, times: usize) {
+fn mix(list: &mut [(usize, i64)], times: usize) {
     let n = list.len();
     for _ in 0..times {
         for p in 0..n {
@@ -81,7 +103,7 @@ fn mix(list: &mut Vec<(usize, i64)>, times: usize) {
 }
 
 /// Mixes the provided list once
-fn mix_once(list: &mut Vec<i64>) {
+fn mix_once(list: &mut [i64]) {
     let mut visited = vec![false; list.len()];
 
     let n = list.len();
@@ -118,3 +140,35 @@ fn wrap(i: i64, n: usize) -> usize {
         (i.unsigned_abs() as usize) % n
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+1
+2
+-3
+3
+-2
+0
+4";
+
+    #[test]
+    fn part1_example() {
+        let input = Day20::parse(EXAMPLE).unwrap();
+        assert_eq!(Day20::part1(&input).unwrap(), 3);
+    }
+
+    #[test]
+    fn part2_example() {
+        let input = Day20::parse(EXAMPLE).unwrap();
+        assert_eq!(Day20::part2(&input).unwrap(), 1623178306);
+    }
+
+    #[test]
+    fn bad_lists() {
+        assert_eq!(Day20::parse("1\n2\n3").unwrap_err().expected, "a 0 in the list");
+        assert_eq!(Day20::parse("0").unwrap_err().expected, "at least two numbers");
+    }
+}
@@ -14,49 +14,70 @@
 //! lhs is known, and so we know the value we need for `rhs`. To
 //! balance `rhs`, we need lhs1-rhs1=lhs0. &c.
 
-use std::{collections::HashMap, fs::read_to_string};
+use std::collections::HashMap;
+
+use crate::error::ParseError;
+use crate::parse::normalise;
+use crate::solution::{Solution, Solved};
+
+type Formula = (String, String, String);
 
 #[derive(Clone, Debug)]
-struct Monkey<'a> {
+struct Monkey {
     value: Option<i64>,
-    formula: Option<(&'a str, &'a str, &'a str)>,
+    formula: Option<Formula>,
 }
 
-impl<'a> Monkey<'a> {
-    fn parse(s: &'a str) -> Monkey<'a> {
+impl Monkey {
+    /// Parses a shout, checking that any monkeys it refers to are in the directory
+    fn parse(input: &str, s: &str, directory: &HashMap<String, usize>) -> Result<Monkey, ParseError> {
         let value = s.parse::<i64>();
 
         match value {
-            Ok(x) => Monkey {
+            Ok(x) => Ok(Monkey {
                 value: Some(x),
                 formula: None,
-            },
+            }),
             Err(_) => {
                 let symbols: Vec<&str> = s.split_whitespace().collect();
-                let formula = (symbols[0], symbols[1], symbols[2]);
-                Monkey {
+                let &[lhs, op, rhs] = symbols.as_slice() else {
+                    return Err(ParseError::at(input, s, "a number or a formula such as `abcd + efgh`"));
+                };
+
+                if !["+", "-", "*", "/"].contains(&op) {
+                    return Err(ParseError::at(input, op, "`+`, `-`, `*` or `/`"));
+                }
+                for name in [lhs, rhs] {
+                    if !directory.contains_key(name) {
+                        return Err(ParseError::at(input, name, "the name of a monkey"));
+                    }
+                }
+
+                let formula = (lhs.to_string(), op.to_string(), rhs.to_string());
+                Ok(Monkey {
                     value: None,
                     formula: Some(formula),
-                }
+                })
             }
         }
     }
 }
 
-struct MonkeyGang<'a> {
-    monkeys: Vec<Monkey<'a>>,
-    directory: HashMap<&'a str, usize>,
+#[derive(Clone)]
+pub struct MonkeyGang {
+    monkeys: Vec<Monkey>,
+    directory: HashMap<String, usize>,
 }
 
-impl MonkeyGang<'_> {
+impl MonkeyGang {
     /// Return the value of the monkey with the provided name
     fn eval(&mut self, name: &str) -> i64 {
         let idx = self._find(name);
 
         let value = match self.monkeys[idx].value {
             None => {
-                let (lhs, op, rhs) = self.monkeys[idx].formula.unwrap();
-                combine(op, self.eval(lhs), self.eval(rhs))
+                let (lhs, op, rhs) = self.monkeys[idx].formula.clone().unwrap();
+                combine(&op, self.eval(&lhs), self.eval(&rhs))
             }
             Some(x) => x,
         };
@@ -70,10 +91,10 @@ impl MonkeyGang<'_> {
         self._reset_humn();
 
         let iroot = self._find("root");
-        let (lhs, _, rhs) = self.monkeys[iroot].formula.unwrap();
+        let (lhs, _, rhs) = self.monkeys[iroot].formula.clone().unwrap();
         self.monkeys[iroot] = Monkey {
             value: None,
-            formula: Some((lhs, "-", rhs)),
+            formula: Some((lhs, "-".to_string(), rhs)),
         };
 
         self._balance_node("root", 0)
@@ -82,15 +103,15 @@ impl MonkeyGang<'_> {
     fn _balance_node(&mut self, name: &str, z: i64) -> i64 {
         let idx = self._find(name);
 
-        match self.monkeys[idx].formula {
+        match self.monkeys[idx].formula.clone() {
             Some((lhs, op, rhs)) => {
-                let ldx = self._find(lhs);
-                let rdx = self._find(rhs);
+                let ldx = self._find(&lhs);
+                let rdx = self._find(&rhs);
 
                 if let Some(x) = self.monkeys[ldx].value {
-                    self._balance_node(rhs, solve_for_y(op, z, x))
+                    self._balance_node(&rhs, solve_for_y(&op, z, x))
                 } else if let Some(y) = self.monkeys[rdx].value {
-                    self._balance_node(lhs, solve_for_x(op, z, y))
+                    self._balance_node(&lhs, solve_for_x(&op, z, y))
                 } else {
                     unreachable!()
                 }
@@ -111,8 +132,8 @@ impl MonkeyGang<'_> {
 
         let mut reset = idx == humn_idx;
 
-        if let Some((lhs, _, rhs)) = self.monkeys[idx].formula {
-            reset = self._reset_node(lhs, humn_idx) || self._reset_node(rhs, humn_idx)
+        if let Some((lhs, _, rhs)) = self.monkeys[idx].formula.clone() {
+            reset = self._reset_node(&lhs, humn_idx) || self._reset_node(&rhs, humn_idx)
         }
 
         if reset {
@@ -157,29 +178,83 @@ fn solve_for_y(op: &str, z: i64, x: i64) -> i64 {
     }
 }
 
-fn parse(input: &'_ str) -> MonkeyGang<'_> {
-    let mut directory: HashMap<&str, usize> = HashMap::new();
-    let mut monkeys = Vec::new();
+pub struct Day21;
+
+impl Solution for Day21 {
+    type Input = MonkeyGang;
+    type Output1 = i64;
+    type Output2 = i64;
+
+    fn parse(input: &str) -> Result<MonkeyGang, ParseError> {
+        let input = &normalise(input);
+
+        let mut directory: HashMap<String, usize> = HashMap::new();
+        let mut shouts = Vec::new();
+
+        for (i, l) in input.lines().enumerate() {
+            let (name, shout) = l
+                .split_once(':')
+                .ok_or_else(|| ParseError::after(input, l, "`: `"))?;
+            shouts.push(shout.trim());
+            directory.insert(name.to_string(), i);
+        }
+
+        for name in ["root", "humn"] {
+            if !directory.contains_key(name) {
+                return Err(ParseError::eof(input, format!("a monkey named `{}`", name)));
+            }
+        }
+
+        let monkeys = shouts
+            .iter()
+            .map(|s| Monkey::parse(input, s, &directory))
+            .collect::<Result<Vec<Monkey>, ParseError>>()?;
 
-    for (i, l) in input.lines().enumerate() {
-        let (name, shout) = l.split_once(':').unwrap();
-        let monkey = Monkey::parse(shout.trim());
-        monkeys.push(monkey);
-        directory.insert(name, i);
+        Ok(MonkeyGang { monkeys, directory })
     }
 
-    MonkeyGang { monkeys, directory }
-}
+    fn part1(gang: &MonkeyGang) -> Solved<i64> {
+        let mut monkeys = gang.clone();
+        Ok(monkeys.eval("root"))
+    }
 
-pub fn part1() -> i64 {
-    let input = read_to_string("data/day21.txt").unwrap();
-    let mut monkeys = parse(&input);
-    monkeys.eval("root")
+    fn part2(gang: &MonkeyGang) -> Solved<i64> {
+        let mut gang = gang.clone();
+        gang.eval("root");
+        Ok(gang.balance())
+    }
 }
 
-pub fn part2() -> i64 {
-    let input = read_to_string("data/day21.txt").unwrap();
-    let mut gang = parse(&input);
-    gang.eval("root");
-    gang.balance()
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+root: pppw + sjmn
+dbpl: 5
+cczh: sllz + lgvd
+zczc: 2
+ptdq: humn - dvpt
+dvpt: 3
+lfqf: 4
+humn: 5
+ljgn: 2
+sjmn: drzm * dbpl
+sllz: 4
+pppw: cczh / lfqf
+lgvd: ljgn * ptdq
+drzm: hmdt - zczc
+hmdt: 32";
+
+    #[test]
+    fn part1_example() {
+        let input = Day21::parse(EXAMPLE).unwrap();
+        assert_eq!(Day21::part1(&input).unwrap(), 152);
+    }
+
+    #[test]
+    fn part2_example() {
+        let input = Day21::parse(EXAMPLE).unwrap();
+        assert_eq!(Day21::part2(&input).unwrap(), 301);
+    }
 }
@@ -16,7 +16,7 @@
 //!
 //! We use a breadth first search to locate each cube face on the 2D grid,
 //! associating each rotation with a single point corresponding to the top-left
-//! corner of that face. Once we've done that, we know which 50x50 subgrid we'll
+//! corner of that face. Once we've done that, we know which subgrid we'll
 //! end up at if we walk off of a given face in any direction.
 //!
 //! The second key idea involved in the solution of part two is to recognise
@@ -34,11 +34,9 @@
 //! The solution to part one is complicated by the fact that the components of
 //! the solution are reused in part two.
 
-use std::{collections::HashMap, fs::read_to_string, ops::Neg};
+use std::{collections::{HashMap, HashSet}, ops::Neg};
 
-use crate::{grid::Grid, parse::parse_u32};
-
-const SIDE_LENGTH: i32 = 50; // Side-length of each cube face
+use crate::{error::ParseError, grid::Grid, parse::{ints, normalise}, solution::{Solution, Solved}};
 
 // Data classes ---------------------------------------------------------------
 
@@ -130,160 +128,182 @@ impl State {
     }
 }
 
-// Parse ----------------------------------------------------------------------
-fn parse() -> (Map, Position, Instructions) {
-    let input = read_to_string("data/day22.txt").unwrap();
-    let mut lines = input.lines();
-
-    // We'll need to pad each row to the width of the grid,
-    let ncol = input
-        .lines()
-        .take_while(|l| !l.is_empty())
-        .map(|l| l.len())
-        .max()
-        .unwrap();
-
-    let mut grid: Vec<char> = Vec::new();
-    let mut line = lines.next().unwrap();
-    let start_col = line.chars().position(|b| b == '.').unwrap();
-    while !line.is_empty() {
-        let row = format!("{:width$}", line, width = ncol);
-        grid.append(&mut row.chars().collect::<Vec<char>>());
-        line = lines.next().unwrap();
+// Solution -------------------------------------------------------------------
+pub struct Day22;
+
+impl Solution for Day22 {
+    type Input = (Map, Position, Instructions);
+    type Output1 = i32;
+    type Output2 = i32;
+
+    fn parse(input: &str) -> Result<(Map, Position, Instructions), ParseError> {
+        let input = &normalise(input);
+
+        let (map, instructions) = input
+            .split_once("\n\n")
+            .ok_or_else(|| ParseError::eof(input, "a blank line after the map"))?;
+        if let Some(i) = map.find(|c| !" .#\n".contains(c)) {
+            return Err(ParseError::at_byte(input, i, "` `, `.` or `#`"));
+        }
+
+        // Rows end at their last tile, so are padded out to the widest
+        let map = Grid::parse_padded(map, ' ', |c| c)?;
+        let start_col = map
+            .row(0)
+            .iter()
+            .position(|&c| c == '.')
+            .ok_or_else(|| ParseError::at(input, input.lines().next().unwrap(), "an open tile `.` on the first row"))?;
+
+        // Parse instructions
+        let instructions = instructions
+            .lines()
+            .next()
+            .ok_or_else(|| ParseError::eof(input, "a path description"))?;
+        if let Some(i) = instructions.find(|c: char| !(c.is_ascii_digit() || c == 'L' || c == 'R')) {
+            return Err(ParseError::at(input, &instructions[i..(i + 1)], "a number of steps, `L` or `R`"));
+        }
+
+        let steps = ints(input, instructions).collect::<Result<Vec<u32>, ParseError>>()?;
+        let turns = instructions
+            .chars()
+            .filter(|x| ['L', 'R'].contains(x))
+            .collect::<Vec<char>>();
+
+        // Both parts walk the map a face at a time, so every tile must belong
+        // to one of six square faces which fold up into a cube
+        let start = (start_col as i32, 0);
+        let side = side_length(&map);
+        let tiles = map.iter().filter(|&(_, c)| c != ' ').count();
+        if tiles != (6 * side * side) as usize || locate_faces(start_state(start, side).face, &map, side).len() != 6 {
+            return Err(ParseError::at(input, input, "a map which folds into a cube"));
+        }
+
+        Ok((map, start, (steps, turns)))
     }
 
-    let nrow = grid.len() / ncol;
-    let map = Grid::new(grid, nrow);
+    fn part1((grid, start, instructions): &(Map, Position, Instructions)) -> Solved<i32> {
+        let side = side_length(grid);
+        let state = start_state(*start, side);
 
-    // Parse instructions
-    let instructions = lines.next().unwrap();
-    let steps = parse_u32(instructions);
-    let turns = instructions
-        .chars()
-        .filter(|x| ['L', 'R'].contains(x))
-        .collect::<Vec<char>>();
+        let teleporter = |state: State| -> State {
+            let mut pos = state.grid_position();
+            let mut face = state.face;
 
-    (map, (start_col as i32, 0), (steps, turns))
-}
+            loop {
+                pos = step_wrap(&pos, &state.dir, (grid.ncol as i32, grid.nrow as i32));
+                match tile(pos, grid) {
+                    Some('.') => break,
+                    Some('#') => break,
+                    _ => (),
+                }
+            }
 
-pub fn part1() -> i32 {
-    let (grid, start, instructions) = parse();
-    let face = Face {
-        i: Vector { i: 1, j: 0, k: 0 },
-        j: Vector { i: 0, j: 1, k: 0 },
-        k: Vector { i: 0, j: 0, k: 1 },
-        corner: start,
-    };
+            let (x, y) = pos;
+            let offset = (x % side, y % side);
+            face.corner = (side * (x / side), side * (y / side));
 
-    let state = State {
-        offset: (0, 0),
-        face,
-        dir: Direction::Right,
-    };
+            State {
+                offset,
+                face,
+                dir: state.dir,
+            }
+        };
 
-    let teleporter = |state: State| -> State {
-        let mut pos = state.grid_position();
-        let mut face = state.face;
+        Ok(password(state, grid, instructions, side, &teleporter))
+    }
 
-        loop {
-            pos = step_wrap(&pos, &state.dir, (grid.ncol as i32, grid.nrow as i32));
-            match tile(pos, &grid) {
-                Some('.') => break,
-                Some('#') => break,
-                _ => (),
+    fn part2((grid, start, instructions): &(Map, Position, Instructions)) -> Solved<i32> {
+        let side = side_length(grid);
+        let state = start_state(*start, side);
+        let faces = locate_faces(state.face, grid, side);
+
+        let teleporter = |state: State| -> State {
+            let State { offset, face, dir } = state;
+            let Face { i, j, k, corner: _ } = face;
+
+            let next_k = match dir {
+                Direction::Down => -j,
+                Direction::Up => j,
+                Direction::Left => i,
+                Direction::Right => -i,
+            };
+
+            // Parsing checked there's a face on every side of the cube
+            let next_face = &faces[&next_k];
+
+            let next_dir = if k == next_face.j {
+                Direction::Down
+            } else if k == -next_face.j {
+                Direction::Up
+            } else if k == -next_face.i {
+                Direction::Left
+            } else if k == next_face.i {
+                Direction::Right
+            } else {
+                unreachable!()
+            };
+
+            let edge = side - 1;
+            let next_offset = match (dir, next_dir) {
+                (Direction::Down, Direction::Down) => (offset.0, 0),
+                (Direction::Down, Direction::Up) => (edge - offset.0, edge),
+                (Direction::Down, Direction::Left) => (edge, offset.0),
+                (Direction::Down, Direction::Right) => (0, edge - offset.0),
+
+                (Direction::Up, Direction::Down) => (edge - offset.0, 0),
+                (Direction::Up, Direction::Up) => (offset.0, edge),
+                (Direction::Up, Direction::Left) => (edge, edge - offset.0),
+                (Direction::Up, Direction::Right) => (0, offset.0),
+
+                (Direction::Left, Direction::Down) => (offset.1, 0),
+                (Direction::Left, Direction::Up) => (edge - offset.1, edge),
+                (Direction::Left, Direction::Left) => (edge, offset.1),
+                (Direction::Left, Direction::Right) => (0, edge - offset.1),
+
+                (Direction::Right, Direction::Down) => (edge - offset.1, 0),
+                (Direction::Right, Direction::Up) => (offset.1, edge),
+                (Direction::Right, Direction::Left) => (edge, edge - offset.1),
+                (Direction::Right, Direction::Right) => (0, offset.1),
+            };
+
+            State {
+                offset: next_offset,
+                face: *next_face,
+                dir: next_dir,
             }
-        }
+        };
 
-        let (x, y) = pos;
-        let offset = (x % 50, y % 50);
-        face.corner = (50 * (x / 50), 50 * (y / 50));
+        Ok(password(state, grid, instructions, side, &teleporter))
+    }
+}
 
-        State {
-            offset,
-            face,
-            dir: state.dir,
-        }
-    };
+// Helpers --------------------------------------------------------------------
 
-    password(state, &grid, &instructions, &teleporter)
+/// Returns the side-length of each cube face. The net is made of six faces,
+/// so this follows from the number of tiles.
+fn side_length(grid: &Grid<char>) -> i32 {
+    let tiles = grid.iter().filter(|&(_, c)| c != ' ').count();
+
+    ((tiles / 6) as f64).sqrt().round() as i32
 }
 
-pub fn part2() -> i32 {
-    let (grid, start, instructions) = parse();
+/// Returns the state at the start of the path: facing right, on the face
+/// containing the start position
+fn start_state((x, y): Position, side: i32) -> State {
     let face = Face {
         i: Vector { i: 1, j: 0, k: 0 },
         j: Vector { i: 0, j: 1, k: 0 },
         k: Vector { i: 0, j: 0, k: 1 },
-        corner: start,
+        corner: (side * (x / side), side * (y / side)),
     };
-    let faces = locate_faces(face, &grid);
 
-    let state = State {
-        offset: (0, 0),
+    State {
+        offset: (x % side, y % side),
         face,
         dir: Direction::Right,
-    };
-
-    let teleporter = |state: State| -> State {
-        let State { offset, face, dir } = state;
-        let Face { i, j, k, corner: _ } = face;
-
-        let next_k = match dir {
-            Direction::Down => -j,
-            Direction::Up => j,
-            Direction::Left => i,
-            Direction::Right => -i,
-        };
-
-        let next_face = faces.get(&next_k).unwrap();
-
-        let next_dir = if k == next_face.j {
-            Direction::Down
-        } else if k == -next_face.j {
-            Direction::Up
-        } else if k == -next_face.i {
-            Direction::Left
-        } else if k == next_face.i {
-            Direction::Right
-        } else {
-            unreachable!()
-        };
-
-        let edge = SIDE_LENGTH - 1;
-        let next_offset = match (dir, next_dir) {
-            (Direction::Down, Direction::Down) => (offset.0, 0),
-            (Direction::Down, Direction::Up) => (edge - offset.0, edge),
-            (Direction::Down, Direction::Left) => (edge, offset.0),
-            (Direction::Down, Direction::Right) => (0, edge - offset.0),
-
-            (Direction::Up, Direction::Down) => (edge - offset.0, 0),
-            (Direction::Up, Direction::Up) => (offset.0, edge),
-            (Direction::Up, Direction::Left) => (edge, edge - offset.0),
-            (Direction::Up, Direction::Right) => (0, offset.0),
-
-            (Direction::Left, Direction::Down) => (offset.1, 0),
-            (Direction::Left, Direction::Up) => (edge - offset.1, edge),
-            (Direction::Left, Direction::Left) => (edge, offset.1),
-            (Direction::Left, Direction::Right) => (0, edge - offset.1),
-
-            (Direction::Right, Direction::Down) => (edge - offset.1, 0),
-            (Direction::Right, Direction::Up) => (offset.1, edge),
-            (Direction::Right, Direction::Left) => (edge, edge - offset.1),
-            (Direction::Right, Direction::Right) => (0, offset.1),
-        };
-
-        State {
-            offset: next_offset,
-            face: *next_face,
-            dir: next_dir,
-        }
-    };
-
-    password(state, &grid, &instructions, &teleporter)
+    }
 }
 
-// Helpers --------------------------------------------------------------------
-
 /// Return the tile at the given location in the grid
 fn tile((x, y): (i32, i32), grid: &Grid<char>) -> Option<char> {
     if x < 0 || x >= (grid.ncol as i32) || y < 0 || y >= (grid.nrow as i32) {
@@ -298,12 +318,13 @@ fn password(
     state: State,
     grid: &Grid<char>,
     instructions: &Instructions,
+    side: i32,
     teleporter: &impl Fn(State) -> State,
 ) -> i32 {
     let mut state = state;
     let mut turns = instructions.1.iter();
     for &steps in &instructions.0 {
-        state = walk(state, steps, grid, teleporter);
+        state = walk(state, steps, grid, side, teleporter);
         match turns.next() {
             Some('R') => state.dir = state.dir.turn_right(),
             Some('L') => state.dir = state.dir.turn_left(),
@@ -342,14 +363,14 @@ fn step_wrap(p: &Position, dir: &Direction, (bx, by): Position) -> Position {
     (x, y)
 }
 
-fn walk(state: State, steps: u32, grid: &Grid<char>, teleporter: impl Fn(State) -> State) -> State {
+fn walk(state: State, steps: u32, grid: &Grid<char>, side: i32, teleporter: impl Fn(State) -> State) -> State {
     let mut state = state;
     for _ in 0..steps {
         let mut next_state = state;
         next_state.offset = step(&state.offset, &state.dir);
 
         let (x, y) = next_state.offset;
-        if !(0..50).contains(&x) || !(0..50).contains(&y) {
+        if !(0..side).contains(&x) || !(0..side).contains(&y) {
             next_state = teleporter(state);
         }
 
@@ -368,7 +389,16 @@ fn walk(state: State, steps: u32, grid: &Grid<char>, teleporter: impl Fn(State)
 }
 
 // Return all six cube Faces, finding them via BFS
-fn locate_faces(start: Face, grid: &Grid<char>) -> HashMap<Vector, Face> {
+fn locate_faces(start: Face, grid: &Grid<char>, side: i32) -> HashMap<Vector, Face> {
+    // The corners of the blocks of the net which hold a face, which they
+    // must fill
+    let corners: HashSet<Position> = grid
+        .tiles(side as usize, side as usize)
+        .filter(|block| block.nrow == side as usize && block.ncol == side as usize)
+        .filter(|block| block.iter().all(|(_, c)| c != ' '))
+        .map(|block| (block.left as i32, block.top as i32))
+        .collect();
+
     let mut queue = vec![start];
     let mut faces = HashMap::new();
 
@@ -379,49 +409,82 @@ fn locate_faces(start: Face, grid: &Grid<char>) -> HashMap<Vector, Face> {
                 i,
                 j: k,
                 k: -j,
-                corner: (corner.0, corner.1 + 50),
+                corner: (corner.0, corner.1 + side),
             },
             // Up
             Face {
                 i,
                 j: -k,
                 k: j,
-                corner: (corner.0, corner.1 - 50),
+                corner: (corner.0, corner.1 - side),
             },
             // Left
             Face {
                 i: -k,
                 j,
                 k: i,
-                corner: (corner.0 - 50, corner.1),
+                corner: (corner.0 - side, corner.1),
             },
             // Right
             Face {
                 i: k,
                 j,
                 k: -i,
-                corner: (corner.0 + 50, corner.1),
+                corner: (corner.0 + side, corner.1),
             },
         ];
 
         for adj in adjacent {
-            if faces.contains_key(&adj.k) {
-                continue;
-            }
-
-            match tile(adj.corner, grid) {
-                Some('.') => {
-                    faces.insert(adj.k, adj);
-                    queue.push(adj);
-                }
-                Some('#') => {
-                    faces.insert(adj.k, adj);
-                    queue.push(adj);
-                }
-                _ => continue,
+            if !faces.contains_key(&adj.k) && corners.contains(&adj.corner) {
+                faces.insert(adj.k, adj);
+                queue.push(adj);
             }
         }
     }
 
     faces
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "        ...#
+        .#..
+        #...
+        ....
+...#.......#
+........#...
+..#....#....
+..........#.
+        ...#....
+        .....#..
+        .#......
+        ......#.
+
+10R5L5R10L4R5L5";
+
+    #[test]
+    fn part1_example() {
+        let input = Day22::parse(EXAMPLE).unwrap();
+        assert_eq!(Day22::part1(&input).unwrap(), 6032);
+    }
+
+    #[test]
+    fn part2_example() {
+        let input = Day22::parse(EXAMPLE).unwrap();
+        assert_eq!(Day22::part2(&input).unwrap(), 5031);
+    }
+
+    #[test]
+    fn bad_nets() {
+        // Too few tiles to make faces from, and six faces in a row
+        for map in ["...\n\n1", "......\n\n1"] {
+            assert_eq!(Day22::parse(map).err().unwrap().expected, "a map which folds into a cube");
+        }
+
+        // Six faces, but one is missing a tile
+        let error = Day22::parse(&EXAMPLE.replacen("...#\n", "..  \n", 1)).err().unwrap();
+        assert_eq!(error.expected, "a map which folds into a cube");
+    }
+}
@@ -10,151 +10,154 @@
 //! we should be able to save a bunch of computations by excluding
 //! elves when they stop. We reintroduce them when needed by checking
 //! for new neighbours of moving elves.
+//!
+//! The elves are kept in a sparse grid, since they spread out over an area
+//! which isn't known in advance.
+
+use crate::grid::{Position, SparseGrid, SURROUNDING};
+use crate::error::ParseError;
+use crate::parse::normalise;
+use crate::solution::{Solution, Solved};
+
+// N, S, W, E as (row, col) offsets
+const PROPOSALS: [Position; 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];
+
+const CONDITIONS: [[(isize, isize); 3]; 4] = [
+    [(-1, -1), (-1, 0), (-1, 1)], // NW, N, NE
+    [(1, -1), (1, 0), (1, 1)],    // SW, S, SE
+    [(-1, -1), (0, -1), (1, -1)], // NW, W, SW
+    [(-1, 1), (0, 1), (1, 1)],    // NE, E, SE
+];
 
-use std::fs::read_to_string;
-
-use crate::grid::Grid;
-
-type Position = (i32, i32);
-
-const MARGIN: usize = 100;
-
-// N, S, W, E
-const PROPOSALS: [Position; 4] = [(0, -1), (0, 1), (-1, 0), (1, 0)];
+pub struct Day23;
 
-const CONDITIONS: [[Position; 3]; 4] = [
-    [(-1, -1), (0, -1), (1, -1)], // NW, N, NE
-    [(-1, 1), (0, 1), (1, 1)],    // SW, S, SE
-    [(-1, -1), (-1, 0), (-1, 1)], // NW, W, SW
-    [(1, -1), (1, 0), (1, 1)],    // NE, E, SE
-];
+impl Solution for Day23 {
+    type Input = (Vec<Position>, SparseGrid<char>);
+    type Output1 = i64;
+    type Output2 = i64;
 
-const NEIGHBOURS: [Position; 8] = [
-    (-1, -1),
-    (-1, 0),
-    (-1, 1),
-    (0, -1),
-    (0, 1),
-    (1, -1),
-    (1, 0),
-    (1, 1),
-];
+    fn parse(input: &str) -> Result<(Vec<Position>, SparseGrid<char>), ParseError> {
+        let input = &normalise(input);
 
-fn parse() -> (Vec<Position>, Grid<char>) {
-    let input = read_to_string("data/day23.txt").unwrap();
-    let mut elves = Vec::new();
+        if let Some(i) = input.find(|c: char| !".#\n".contains(c)) {
+            return Err(ParseError::at_byte(input, i, "`.` or `#`"));
+        }
 
-    let grid_width = input.lines().next().unwrap().len() + 2 * MARGIN;
-    let mut grid = Grid::new(vec!['.'; grid_width * grid_width], grid_width);
+        let mut elves = Vec::new();
+        let mut grid = SparseGrid::new('.');
 
-    for (y, line) in input.lines().enumerate() {
-        for (x, ch) in line.chars().enumerate() {
-            if ch == '#' {
-                elves.push(((x + MARGIN) as i32, (y + MARGIN) as i32));
-                grid.set(y + MARGIN, x + MARGIN, '#');
+        for (y, line) in input.lines().enumerate() {
+            for (x, ch) in line.chars().enumerate() {
+                if ch == '#' {
+                    elves.push((y as i64, x as i64));
+                    grid.set((y as i64, x as i64), '#');
+                }
             }
         }
-    }
 
-    (elves, grid)
-}
-
-pub fn part1() -> i32 {
-    let (mut elves, mut grid) = parse();
+        if elves.is_empty() {
+            return Err(ParseError::eof(input, "a map of the elves"));
+        }
 
-    for step in (0..4).cycle().take(10) {
-        let mut proposals = gather_proposals(&elves, &grid, step);
-        resolve_proposals(&mut proposals, &elves);
-        exectue_proposals(&proposals, &mut elves, &mut grid);
+        Ok((elves, grid))
     }
 
-    let x: Vec<i32> = elves.iter().map(|(x, _)| *x).collect();
-    let y: Vec<i32> = elves.iter().map(|(_, y)| *y).collect();
+    fn part1((elves, grid): &(Vec<Position>, SparseGrid<char>)) -> Solved<i64> {
+        let (mut elves, mut grid) = (elves.clone(), grid.clone());
 
-    let width = x.iter().max().unwrap() - x.iter().min().unwrap();
-    let height = y.iter().max().unwrap() - y.iter().min().unwrap();
+        for step in (0..4).cycle().take(10) {
+            let mut proposals = gather_proposals(&elves, &grid, step);
+            resolve_proposals(&mut proposals, &elves);
+            exectue_proposals(&proposals, &mut elves, &mut grid);
+        }
 
-    (height + 1) * (width + 1) - (elves.len() as i32)
-}
+        let ((i0, j0), (i1, j1)) = grid.bounds().unwrap();
+
+        Ok((i1 - i0 + 1) * (j1 - j0 + 1) - (elves.len() as i64))
+    }
 
-pub fn part2() -> i32 {
-    let (mut elves, mut grid) = parse();
+    fn part2((elves, grid): &(Vec<Position>, SparseGrid<char>)) -> Solved<i64> {
+        let (mut elves, mut grid) = (elves.clone(), grid.clone());
 
-    let mut s = 0;
-    for step in (0..4).cycle() {
-        s += 1;
-        let mut proposals = gather_proposals(&elves, &grid, step);
-        resolve_proposals(&mut proposals, &elves);
-        let any_moved = exectue_proposals(&proposals, &mut elves, &mut grid);
+        let mut s = 0;
+        for step in (0..4).cycle() {
+            s += 1;
+            let mut proposals = gather_proposals(&elves, &grid, step);
+            resolve_proposals(&mut proposals, &elves);
+            let any_moved = exectue_proposals(&proposals, &mut elves, &mut grid);
 
-        if !any_moved {
-            break;
+            if !any_moved {
+                break;
+            }
         }
-    }
 
-    s
+        Ok(s)
+    }
 }
 
 /// Return a grid containing movement proposals for each elf. The proposal at (i,j) in this grid
 /// is for the elf at (i,j) in the elf grid.
 fn gather_proposals(
     elves: &Vec<Position>,
-    grid: &Grid<char>,
+    grid: &SparseGrid<char>,
     step: usize,
-) -> Grid<Option<Position>> {
-    let mut proposals: Grid<Option<Position>> = Grid::new(vec![None; grid.len()], grid.nrow);
-    for i in (0..4).cycle().skip(step).take(4) {
-        let condition = CONDITIONS[i];
-
-        for (ex, ey) in elves {
-            if proposals.get(*ey as usize, *ex as usize).is_some() {
-                continue; // Already recieved a proposal
-            };
+) -> SparseGrid<Option<Position>> {
+    let conditions = CONDITIONS.map(|condition| occupied(&condition, |_| true));
+
+    let mut proposals = SparseGrid::new(None);
+    for &elf in elves {
+        let neighbours = occupied(&SURROUNDING, |(di, dj)| grid.get((elf.0 + di as i64, elf.1 + dj as i64)) != '.');
+        if neighbours == 0 {
+            continue; // Nobody nearby, so the elf stays put
+        }
 
-            let elf_moves = count_free((*ex, *ey), &NEIGHBOURS, grid) < 8;
-            let proposal_ok = count_free((*ex, *ey), &condition, grid) == condition.len();
+        let proposal = (0..4)
+            .cycle()
+            .skip(step)
+            .take(4)
+            .find(|&i| neighbours & conditions[i] == 0);
 
-            if proposal_ok && elf_moves {
-                proposals.set(*ey as usize, *ex as usize, Some(PROPOSALS[i]));
-            }
+        if let Some(i) = proposal {
+            proposals.set(elf, Some(PROPOSALS[i]));
         }
     }
 
     proposals
 }
 
-/// Return the number of free squares among the neighbours defined by `delta`
-fn count_free((x, y): Position, delta: &[Position], grid: &Grid<char>) -> usize {
-    delta
+/// Returns a bit set of the offsets in `SURROUNDING` which are among `delta`
+/// and which are occupied
+fn occupied(delta: &[(isize, isize)], is_occupied: impl Fn((isize, isize)) -> bool) -> u8 {
+    SURROUNDING
         .iter()
-        .map(|(dx, dy)| (x + dx, y + dy))
-        .filter(|(x, y)| grid.get(*y as usize, *x as usize) == '.')
-        .count()
+        .enumerate()
+        .filter(|&(_, &d)| delta.contains(&d) && is_occupied(d))
+        .fold(0, |set, (i, _)| set | 1 << i)
 }
 
 /// Remove clashing proposals from the proposal grid
-fn resolve_proposals(proposals: &mut Grid<Option<Position>>, elves: &Vec<Position>) {
-    for (ex, ey) in elves {
-        if let Some((px, py)) = proposals.get(*ey as usize, *ex as usize) {
+fn resolve_proposals(proposals: &mut SparseGrid<Option<Position>>, elves: &Vec<Position>) {
+    for &(ei, ej) in elves {
+        if let Some((pi, pj)) = proposals.get((ei, ej)) {
             // Each elf only needs to cross-check the proposals of 3 competitors
-            let competitors: [Position; 3] = if px == 0 {
-                [(*ex, ey + 2 * py), (ex - 1, ey + py), (ex + 1, ey + py)]
+            let competitors: [Position; 3] = if pj == 0 {
+                [(ei + 2 * pi, ej), (ei + pi, ej - 1), (ei + pi, ej + 1)]
             } else {
-                [(ex + 2 * px, *ey), (ex + px, ey - 1), (ex + px, ey + 1)]
+                [(ei, ej + 2 * pj), (ei - 1, ej + pj), (ei + 1, ej + pj)]
             };
 
             let mut spoiled = false;
-            for (cx, cy) in competitors {
-                if let Some((cpx, cpy)) = proposals.get(cy as usize, cx as usize) {
-                    if (ex + px, ey + py) == (cx + cpx, cy + cpy) {
-                        proposals.set(cy as usize, cx as usize, None);
+            for (ci, cj) in competitors {
+                if let Some((cpi, cpj)) = proposals.get((ci, cj)) {
+                    if (ei + pi, ej + pj) == (ci + cpi, cj + cpj) {
+                        proposals.set((ci, cj), None);
                         spoiled = true;
                     }
                 }
             }
 
             if spoiled {
-                proposals.set(*ey as usize, *ex as usize, None);
+                proposals.set((ei, ej), None);
             }
         }
     }
@@ -162,21 +165,47 @@ fn resolve_proposals(proposals: &mut Grid<Option<Position>>, elves: &Vec<Positio
 
 /// Execute proposals, moving the elves on the grid accordingly
 fn exectue_proposals(
-    proposals: &Grid<Option<Position>>,
+    proposals: &SparseGrid<Option<Position>>,
     elves: &mut [Position],
-    grid: &mut Grid<char>,
+    grid: &mut SparseGrid<char>,
 ) -> bool {
     let mut moved = false;
     for e in elves.iter_mut() {
-        let (ex, ey) = *e;
-        if let Some((px, py)) = proposals.get(ey as usize, ex as usize) {
-            let (nx, ny) = (ex + px, ey + py);
-            grid.set(ey as usize, ex as usize, '.');
-            grid.set(ny as usize, nx as usize, '#');
-            *e = (ex + px, ey + py);
+        let (ei, ej) = *e;
+        if let Some((pi, pj)) = proposals.get((ei, ej)) {
+            let next = (ei + pi, ej + pj);
+            grid.set((ei, ej), '.');
+            grid.set(next, '#');
+            *e = next;
             moved = true;
         }
     }
 
     moved
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+....#..
+..###.#
+#...#.#
+.#...##
+#.###..
+##.#.##
+.#..#..";
+
+    #[test]
+    fn part1_example() {
+        let input = Day23::parse(EXAMPLE).unwrap();
+        assert_eq!(Day23::part1(&input).unwrap(), 110);
+    }
+
+    #[test]
+    fn part2_example() {
+        let input = Day23::parse(EXAMPLE).unwrap();
+        assert_eq!(Day23::part2(&input).unwrap(), 20);
+    }
+}
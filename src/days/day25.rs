@@ -2,32 +2,46 @@
 //!
 //! An easy problem to finish :)
 
-use std::fs::read_to_string;
+use crate::error::ParseError;
+use crate::parse::normalise;
+use crate::solution::{Solution, Solved};
 
-pub fn part1() -> String {
-    let input = read_to_string("data/day25.txt").unwrap();
+pub struct Day25;
 
-    let x: i64 = input.lines().map(from_snafu).sum();
-    to_snafu(x)
-}
+impl Solution for Day25 {
+    type Input = Vec<i64>;
+    type Output1 = String;
+    type Output2 = i64;
+
+    fn parse(input: &str) -> Result<Vec<i64>, ParseError> {
+        let input = &normalise(input);
 
-pub fn part2() -> i64 {
-    0
+        input.lines().map(|l| from_snafu(input, l)).collect()
+    }
+
+    fn part1(numbers: &Vec<i64>) -> Solved<String> {
+        let x: i64 = numbers.iter().sum();
+        Ok(to_snafu(x))
+    }
+
+    fn part2(_: &Vec<i64>) -> Solved<i64> {
+        Ok(0)
+    }
 }
 
-fn from_snafu(x: &str) -> i64 {
-    let n = x.len() - 1;
-    x.chars()
-        .map(|ch| match ch {
-            '=' => -2,
-            '-' => -1,
-            '0' => 0,
-            '1' => 1,
-            '2' => 2,
-            _ => unreachable!(),
+fn from_snafu(input: &str, x: &str) -> Result<i64, ParseError> {
+    let n = x.len().saturating_sub(1);
+    x.char_indices()
+        .map(|(p, ch)| match ch {
+            '=' => Ok(-2),
+            '-' => Ok(-1),
+            '0' => Ok(0),
+            '1' => Ok(1),
+            '2' => Ok(2),
+            _ => Err(ParseError::at(input, &x[p..(p + ch.len_utf8())], "a SNAFU digit")),
         })
         .enumerate()
-        .map(|(p, i)| i * 5_i64.pow((n - p) as u32))
+        .map(|(p, i)| Ok(i? * 5_i64.pow((n - p) as u32)))
         .sum()
 }
 
@@ -55,3 +69,54 @@ fn to_snafu(x: i64) -> String {
 
     snafu.chars().rev().collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+1=-0-2
+12111
+2=0=
+21
+2=01
+111
+20012
+112
+1=-1=
+1-12
+12
+1=
+122";
+
+    #[test]
+    fn part1_example() {
+        let input = Day25::parse(EXAMPLE).unwrap();
+        assert_eq!(Day25::part1(&input).unwrap(), "2=-1=0");
+    }
+
+    /// Includes numbers which need a carry into the next SNAFU digit
+    #[test]
+    fn snafu_conversions() {
+        let numbers = [
+            (1, "1"),
+            (2, "2"),
+            (3, "1="),
+            (4, "1-"),
+            (5, "10"),
+            (8, "2="),
+            (9, "2-"),
+            (10, "20"),
+            (15, "1=0"),
+            (20, "1-0"),
+            (2022, "1=11-2"),
+            (12345, "1-0---0"),
+            (314159265, "1121-1110-1=0"),
+        ];
+
+        for (decimal, snafu) in numbers {
+            assert_eq!(to_snafu(decimal), snafu);
+            assert_eq!(from_snafu(snafu, snafu).unwrap(), decimal);
+        }
+    }
+}
@@ -1,25 +1,98 @@
-pub mod day01;
-pub mod day02;
-pub mod day03;
-pub mod day04;
-pub mod day05;
-pub mod day06;
-pub mod day07;
-pub mod day08;
-pub mod day09;
-pub mod day10;
-pub mod day11;
-pub mod day12;
-pub mod day13;
-pub mod day14;
-pub mod day15;
-pub mod day16;
-pub mod day17;
-pub mod day18;
-pub mod day19;
-pub mod day20;
-pub mod day21;
-pub mod day22;
-pub mod day23;
-pub mod day24;
-pub mod day25;
\ No newline at end of file
+//! The daily puzzles, and a registry through which to look them up
+//!
+//! To add a new day, write a module with a type implementing `Solution` and
+//! add a line for it to the `register!` block at the bottom of this file.
+
+use std::error::Error;
+
+use crate::bench::Bench;
+use crate::error::ParseError;
+use crate::picture::Picture;
+use crate::solution::{self, Params, Report, Solution};
+
+type Run = fn(&str, &Params, &[u8], &Bench) -> Result<Report, Box<dyn Error>>;
+type Render = fn(&str, &Params, u8) -> Result<Option<Picture>, Box<dyn Error>>;
+
+/// A registered day, with the type of its solution erased
+pub struct Day {
+    pub number: u8,
+    run: Run,
+    render: Render,
+}
+
+impl Day {
+    const fn new<S: Solution>(number: u8) -> Self {
+        Day {
+            number,
+            run: solution::solve::<S>,
+            render: solution::render::<S>,
+        }
+    }
+
+    /// Returns the solutions to the given parts of the puzzle, timed as
+    /// `bench` asks, or an error if the input couldn't be parsed or a
+    /// parameter couldn't be set
+    pub fn solve(&self, input: &str, params: &Params, parts: &[u8], bench: &Bench) -> Result<Report, Box<dyn Error>> {
+        (self.run)(input, params, parts, bench).map_err(|e| self._on_day(e))
+    }
+
+    /// Returns a picture of how the given part of the puzzle was solved, or
+    /// `None` if this day doesn't draw one
+    pub fn render(&self, input: &str, params: &Params, part: u8) -> Result<Option<Picture>, Box<dyn Error>> {
+        (self.render)(input, params, part).map_err(|e| self._on_day(e))
+    }
+
+    /// Adds this day's number to an error
+    fn _on_day(&self, error: Box<dyn Error>) -> Box<dyn Error> {
+        match error.downcast::<ParseError>() {
+            Ok(e) => Box::new(e.on_day(self.number)),
+            Err(e) => format!("day {:02}: {}", self.number, e).into(),
+        }
+    }
+}
+
+/// Returns every registered day, in order
+pub fn all() -> &'static [Day] {
+    DAYS
+}
+
+/// Returns the day with the given number, if it has been registered
+pub fn get(number: u8) -> Option<&'static Day> {
+    all().iter().find(|d| d.number == number)
+}
+
+macro_rules! register {
+    ($($number:literal => $module:ident::$solution:ident),* $(,)?) => {
+        $(pub mod $module;)*
+
+        static DAYS: &[Day] = &[$(Day::new::<$module::$solution>($number)),*];
+    };
+}
+
+register! {
+    1 => day01::Day01,
+    2 => day02::Day02,
+    3 => day03::Day03,
+    4 => day04::Day04,
+    5 => day05::Day05,
+    6 => day06::Day06,
+    7 => day07::Day07,
+    8 => day08::Day08,
+    9 => day09::Day09,
+    10 => day10::Day10,
+    11 => day11::Day11,
+    12 => day12::Day12,
+    13 => day13::Day13,
+    14 => day14::Day14,
+    15 => day15::Day15,
+    16 => day16::Day16,
+    17 => day17::Day17,
+    18 => day18::Day18,
+    19 => day19::Day19,
+    20 => day20::Day20,
+    21 => day21::Day21,
+    22 => day22::Day22,
+    23 => day23::Day23,
+    24 => day24::Day24,
+    25 => day25::Day25,
+}
@@ -0,0 +1,101 @@
+//! Errors raised while parsing puzzle input
+//!
+//! Most parsers work on slices of the original input, so rather than
+//! threading line and column numbers through every parser we locate the
+//! offending token from its position in memory relative to the input.
+
+use std::{error::Error, fmt};
+
+/// An error describing where and why a puzzle input failed to parse
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub day: Option<u8>,
+    pub line: usize,
+    pub column: usize,
+    pub expected: String,
+    pub found: String,
+    source_line: String,
+}
+
+impl ParseError {
+    /// Returns an error located at `token`, which must be a slice of `input`
+    pub fn at(input: &str, token: &str, expected: impl Into<String>) -> Self {
+        let offset = (token.as_ptr() as usize).wrapping_sub(input.as_ptr() as usize);
+        debug_assert!(offset <= input.len(), "token is not a slice of the input");
+
+        Self::locate(input, offset.min(input.len()), token, expected.into())
+    }
+
+    /// Returns an error located just after `token`, which must be a slice of
+    /// `input`. Useful when something is missing from the end of a line.
+    pub fn after(input: &str, token: &str, expected: impl Into<String>) -> Self {
+        Self::at(input, &token[token.len()..], expected)
+    }
+
+    /// Returns an error located at the byte with the given offset into `input`
+    pub fn at_byte(input: &str, offset: usize, expected: impl Into<String>) -> Self {
+        let mut offset = offset.min(input.len());
+        while !input.is_char_boundary(offset) {
+            offset -= 1;
+        }
+
+        let found = input[offset..].chars().take(1).collect::<String>();
+        Self::locate(input, offset, &found, expected.into())
+    }
+
+    /// Returns an error located at the end of `input`
+    pub fn eof(input: &str, expected: impl Into<String>) -> Self {
+        Self::locate(input, input.len(), "", expected.into())
+    }
+
+    /// Records the day whose input failed to parse
+    pub fn on_day(mut self, day: u8) -> Self {
+        self.day = Some(day);
+        self
+    }
+
+    fn locate(input: &str, offset: usize, found: &str, expected: String) -> Self {
+        let before = &input[..offset];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+
+        // Don't let a multi-line token run on past the end of the first line
+        let found = match found.find('\n') {
+            Some(0) => "\n",
+            Some(i) => &found[..i],
+            None => found,
+        };
+
+        ParseError {
+            day: None,
+            line: before.matches('\n').count() + 1,
+            column: input[line_start..offset].chars().count() + 1,
+            expected,
+            found: found.to_string(),
+            source_line: input[line_start..].lines().next().unwrap_or("").to_string(),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(day) = self.day {
+            write!(f, "day {:02}, ", day)?;
+        }
+
+        write!(f, "line {}, column {}: expected {}, ", self.line, self.column, self.expected)?;
+        if self.found.is_empty() {
+            writeln!(f, "found nothing")?;
+        } else {
+            writeln!(f, "found `{}`", self.found.escape_debug())?;
+        }
+
+        // Point at the offending token underneath the line it came from
+        let gutter = " ".repeat(self.line.to_string().len());
+        let marker = "^".repeat(self.found.chars().count().max(1));
+        writeln!(f, "{} |", gutter)?;
+        writeln!(f, "{} | {}", self.line, self.source_line.trim_end())?;
+        write!(f, "{} | {}{}", gutter, " ".repeat(self.column - 1), marker)
+    }
+}
+
+impl Error for ParseError {}
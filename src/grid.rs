@@ -1,7 +1,39 @@
-//! A two-dimensional grid
+//! Grids
+//!
+//! `Grid` is dense and of fixed size, indexed from zero. `BitGrid` is a dense
+//! grid of booleans packed into words, for simulations which can work on many
+//! cells at once. `SparseGrid` only stores the elements which differ from its
+//! default, so it can be indexed by any pair of signed coordinates. `GridN` is
+//! dense like `Grid`, but with any number of dimensions.
 
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{BuildHasherDefault, Hasher};
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, Not};
 
-#[derive(Debug)]
+use crate::error::ParseError;
+
+/// Offsets of the four cells orthogonally adjacent to a cell: up, left, down
+/// and right
+pub const ORTHOGONAL: [(isize, isize); 4] = [(-1, 0), (0, -1), (1, 0), (0, 1)];
+
+/// Offsets of all eight cells surrounding a cell, including the diagonals, in
+/// row order
+pub const SURROUNDING: [(isize, isize); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+
+// Grid ------------------------------------------------------------------------
+
+#[derive(Debug, Clone)]
 pub struct Grid<T> {
     elements: Vec<T>,
     pub nrow: usize,
@@ -9,17 +41,14 @@ pub struct Grid<T> {
 }
 
 impl Grid<u8> {
-    pub fn parse(input: &str) -> Self {
-        let elements: Vec<u8> = input.as_bytes()
-            .iter()
-            .filter(|&i| *i > 31) // Remove whitespace
-            .cloned()
-            .collect();
-        
-        let nrow = input.lines().count();
-        let ncol = elements.len() / nrow;
+    /// Returns the grid of ASCII characters drawn by `input`, one row per
+    /// line. Every row must be the same length.
+    pub fn parse(input: &str) -> Result<Self, ParseError> {
+        if let Some(i) = input.find(|c: char| !c.is_ascii()) {
+            return Err(ParseError::at_byte(input, i, "an ASCII character"));
+        }
 
-        Grid { elements, nrow, ncol }
+        Grid::parse_with(input, |c| c as u8)
     }
 }
 
@@ -30,6 +59,58 @@ impl<T: Copy + PartialEq> Grid<T> {
         Grid { elements, nrow, ncol }
     }
 
+    /// Returns the grid drawn by `input`, one row per line, with `cell`
+    /// giving the element for each character. Every row must be the same
+    /// length as the first.
+    pub fn parse_with(input: &str, cell: impl Fn(char) -> T) -> Result<Self, ParseError> {
+        Self::_parse(input, None, cell)
+    }
+
+    /// As `parse_with`, but rows may be of different lengths. Any shorter than
+    /// the longest are padded at the end with `fill`.
+    pub fn parse_padded(input: &str, fill: T, cell: impl Fn(char) -> T) -> Result<Self, ParseError> {
+        Self::_parse(input, Some(fill), cell)
+    }
+
+    fn _parse(input: &str, fill: Option<T>, cell: impl Fn(char) -> T) -> Result<Self, ParseError> {
+        let ncol = match fill {
+            Some(_) => input.lines().map(|l| l.chars().count()).max(),
+            None => input.lines().next().map(|l| l.chars().count()),
+        };
+        let ncol = ncol
+            .filter(|&n| n > 0)
+            .ok_or_else(|| ParseError::eof(input, "a grid"))?;
+
+        let mut elements = Vec::new();
+        let mut nrow = 0;
+        for line in input.lines() {
+            let width = line.chars().count();
+            if width != ncol && fill.is_none() {
+                return Err(ParseError::at(input, line, format!("a row of {} cells", ncol)));
+            }
+
+            elements.extend(line.chars().map(&cell));
+            elements.extend(fill.into_iter().cycle().take(ncol - width));
+            nrow += 1;
+        }
+
+        Ok(Grid { elements, nrow, ncol })
+    }
+
+    /// Returns the grid drawn as text, one row per line, with `cell` giving
+    /// the character for each element
+    pub fn render_with(&self, cell: impl Fn(T) -> char) -> String {
+        let mut text = String::with_capacity((self.ncol + 1) * self.nrow);
+        for (i, row) in self.rows().enumerate() {
+            if i > 0 {
+                text.push('\n');
+            }
+            text.extend(row.iter().map(|&x| cell(x)));
+        }
+
+        text
+    }
+
     /// Returns the element at location (i, j)
     pub fn get(&self, i: usize, j: usize) -> T {
         self.elements[i*self.ncol + j]
@@ -52,16 +133,135 @@ impl<T: Copy + PartialEq> Grid<T> {
         self.elements.len()
     }
 
-    /// Returns indices of elements neighbouring (i, j)
-    pub fn neighbours(&self, i: usize, j:usize) -> Vec<(usize, usize)> {
-        let mut nb = Vec::with_capacity(4);
-        
-        if i > 0 { nb.push((i-1, j)) }
-        if j > 0 { nb.push((i, j-1)) }
-        if i < (self.nrow-1) { nb.push((i+1, j)) }
-        if j < (self.ncol-1) { nb.push((i, j+1)) }
+    /// Returns true if the grid has no elements
+    pub fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+
+    /// Returns the element at location (i, j), or `None` if that lies outside
+    /// the grid
+    pub fn get_checked(&self, i: usize, j: usize) -> Option<T> {
+        (i < self.nrow && j < self.ncol).then(|| self.get(i, j))
+    }
+
+    /// Returns the location `(di, dj)` away from (i, j), if it lies within
+    /// the grid
+    pub fn offset(&self, (i, j): (usize, usize), (di, dj): (isize, isize)) -> Option<(usize, usize)> {
+        let i = i.checked_add_signed(di).filter(|&i| i < self.nrow)?;
+        let j = j.checked_add_signed(dj).filter(|&j| j < self.ncol)?;
+
+        Some((i, j))
+    }
+
+    /// Returns the element `(di, dj)` away from (i, j), if it lies within the
+    /// grid
+    pub fn get_offset(&self, i: usize, j: usize, delta: (isize, isize)) -> Option<T> {
+        self.offset((i, j), delta).map(|(i, j)| self.get(i, j))
+    }
+
+    /// Returns the locations of the elements orthogonally adjacent to (i, j)
+    pub fn neighbours(&self, i: usize, j: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+        ORTHOGONAL.iter().filter_map(move |&d| self.offset((i, j), d))
+    }
+
+    /// Returns the locations of the elements surrounding (i, j), including
+    /// the diagonals
+    pub fn surrounding(&self, i: usize, j: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+        SURROUNDING.iter().filter_map(move |&d| self.offset((i, j), d))
+    }
+
+    /// Returns the locations met moving from (i, j) in steps of `(di, dj)`
+    /// until the edge of the grid, not including (i, j) itself
+    pub fn ray(&self, i: usize, j: usize, delta: (isize, isize)) -> impl Iterator<Item = (usize, usize)> + '_ {
+        std::iter::successors(self.offset((i, j), delta), move |&pos| self.offset(pos, delta))
+    }
+
+    /// Returns the elements of row `i`
+    pub fn row(&self, i: usize) -> &[T] {
+        &self.elements[i*self.ncol..(i + 1)*self.ncol]
+    }
 
-        nb  
+    /// Returns the elements of column `j`, from top to bottom
+    pub fn column(&self, j: usize) -> impl DoubleEndedIterator<Item = T> + '_ {
+        self.elements.iter().skip(j).step_by(self.ncol.max(1)).copied()
+    }
+
+    /// Returns each row of the grid in turn
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        (0..self.nrow).map(|i| self.row(i))
+    }
+
+    /// Returns each column of the grid in turn
+    pub fn columns(&self) -> impl Iterator<Item = impl DoubleEndedIterator<Item = T> + '_> {
+        (0..self.ncol).map(|j| self.column(j))
+    }
+
+    /// Returns the location and value of each element, in row order
+    pub fn iter(&self) -> impl Iterator<Item = ((usize, usize), T)> + '_ {
+        self.elements.iter().enumerate().map(|(idx, &x)| (self._as_rowcol(idx), x))
+    }
+
+    /// Returns a borrowed view of the `nrow` by `ncol` rectangle with its
+    /// top-left corner at (i, j), clipped to the edges of the grid
+    pub fn view(&self, i: usize, j: usize, nrow: usize, ncol: usize) -> GridView<'_, T> {
+        let (top, left) = (i.min(self.nrow), j.min(self.ncol));
+        GridView {
+            grid: self,
+            top,
+            left,
+            nrow: nrow.min(self.nrow - top),
+            ncol: ncol.min(self.ncol - left),
+        }
+    }
+
+    /// Returns views of the grid cut into `nrow` by `ncol` blocks, in row
+    /// order. Blocks along the bottom and right edges are smaller if the grid
+    /// doesn't divide evenly.
+    pub fn tiles(&self, nrow: usize, ncol: usize) -> impl Iterator<Item = GridView<'_, T>> {
+        assert!(nrow > 0 && ncol > 0, "tiles must have at least one row and column");
+
+        (0..self.nrow)
+            .step_by(nrow)
+            .flat_map(move |i| (0..self.ncol).step_by(ncol).map(move |j| self.view(i, j, nrow, ncol)))
+    }
+
+    /// Returns the grid with its rows and columns swapped
+    pub fn transpose(&self) -> Grid<T> {
+        self._remap(self.ncol, self.nrow, |i, j| (j, i))
+    }
+
+    /// Returns the grid turned 90 degrees clockwise
+    pub fn rotate_clockwise(&self) -> Grid<T> {
+        self._remap(self.ncol, self.nrow, |i, j| (self.nrow - 1 - j, i))
+    }
+
+    /// Returns the grid turned 90 degrees anticlockwise
+    pub fn rotate_anticlockwise(&self) -> Grid<T> {
+        self._remap(self.ncol, self.nrow, |i, j| (j, self.ncol - 1 - i))
+    }
+
+    /// Returns the grid mirrored left to right
+    pub fn flip_horizontal(&self) -> Grid<T> {
+        self._remap(self.nrow, self.ncol, |i, j| (i, self.ncol - 1 - j))
+    }
+
+    /// Returns the grid mirrored top to bottom
+    pub fn flip_vertical(&self) -> Grid<T> {
+        self._remap(self.nrow, self.ncol, |i, j| (self.nrow - 1 - i, j))
+    }
+
+    /// Returns an `nrow` by `ncol` grid whose element at (i, j) is taken
+    /// from this grid at `source(i, j)`
+    fn _remap(&self, nrow: usize, ncol: usize, source: impl Fn(usize, usize) -> (usize, usize)) -> Grid<T> {
+        let elements = (0..nrow)
+            .flat_map(|i| (0..ncol).map(move |j| (i, j)))
+            .map(|(i, j)| {
+                let (si, sj) = source(i, j);
+                self.get(si, sj)
+            })
+            .collect();
+
+        Grid { elements, nrow, ncol }
     }
 
     /// Converts a position in the vector into a (row, col) location
@@ -69,3 +269,758 @@ impl<T: Copy + PartialEq> Grid<T> {
         (idx / self.ncol, idx % self.ncol)
     }
 }
+
+impl fmt::Display for Grid<char> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.render_with(|c| c))
+    }
+}
+
+/// Draws each byte as the ASCII character it stands for
+impl fmt::Display for Grid<u8> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.render_with(|b| b as char))
+    }
+}
+
+/// A rectangle borrowed from a `Grid`, with its own coordinates starting from
+/// zero at its top-left corner
+#[derive(Debug, Clone, Copy)]
+pub struct GridView<'a, T> {
+    grid: &'a Grid<T>,
+    /// The row of the parent grid holding the top of the view
+    pub top: usize,
+    /// The column of the parent grid holding the left of the view
+    pub left: usize,
+    pub nrow: usize,
+    pub ncol: usize,
+}
+
+impl<T: Copy + PartialEq> GridView<'_, T> {
+    /// Returns the element at location (i, j) of the view
+    pub fn get(&self, i: usize, j: usize) -> T {
+        assert!(i < self.nrow && j < self.ncol, "({}, {}) is outside the view", i, j);
+        self.grid.get(self.top + i, self.left + j)
+    }
+
+    /// Returns the element at location (i, j) of the view, or `None` if that
+    /// lies outside it
+    pub fn get_checked(&self, i: usize, j: usize) -> Option<T> {
+        (i < self.nrow && j < self.ncol).then(|| self.get(i, j))
+    }
+
+    /// Returns the location and value of each element, in row order
+    pub fn iter(&self) -> impl Iterator<Item = ((usize, usize), T)> + '_ {
+        (0..self.nrow)
+            .flat_map(|i| (0..self.ncol).map(move |j| (i, j)))
+            .map(|(i, j)| ((i, j), self.get(i, j)))
+    }
+
+    /// Returns a copy of the elements within the view
+    pub fn to_grid(&self) -> Grid<T> {
+        Grid {
+            elements: self.iter().map(|(_, x)| x).collect(),
+            nrow: self.nrow,
+            ncol: self.ncol,
+        }
+    }
+}
+
+// Bit grid --------------------------------------------------------------------
+
+const WORD: usize = u64::BITS as usize;
+
+/// A dense grid of booleans, with each row packed into 64 bit words. Column
+/// `j` of a row is bit `j % 64` of word `j / 64`, and any bits beyond the last
+/// column are always off.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BitGrid {
+    words: Vec<u64>,
+    stride: usize,
+    pub nrow: usize,
+    pub ncol: usize,
+}
+
+impl BitGrid {
+    /// Returns a grid of `nrow` rows and `ncol` columns with every bit off
+    pub fn new(nrow: usize, ncol: usize) -> Self {
+        let stride = ncol.div_ceil(WORD);
+
+        BitGrid { words: vec![0; nrow * stride], stride, nrow, ncol }
+    }
+
+    /// Returns a grid with the bit at (i, j) on wherever `f(i, j)` is true
+    pub fn from_fn(nrow: usize, ncol: usize, f: impl Fn(usize, usize) -> bool) -> Self {
+        let mut grid = BitGrid::new(nrow, ncol);
+        for i in 0..nrow {
+            for j in 0..ncol {
+                grid.set(i, j, f(i, j));
+            }
+        }
+
+        grid
+    }
+
+    pub fn get(&self, i: usize, j: usize) -> bool {
+        assert!(i < self.nrow && j < self.ncol, "({}, {}) is outside the grid", i, j);
+        self.words[i * self.stride + j / WORD] >> (j % WORD) & 1 == 1
+    }
+
+    pub fn set(&mut self, i: usize, j: usize, x: bool) {
+        assert!(i < self.nrow && j < self.ncol, "({}, {}) is outside the grid", i, j);
+        let word = &mut self.words[i * self.stride + j / WORD];
+        match x {
+            true => *word |= 1 << (j % WORD),
+            false => *word &= !(1 << (j % WORD)),
+        }
+    }
+
+    /// Returns the words holding row `i`
+    pub fn row(&self, i: usize) -> &[u64] {
+        &self.words[(i * self.stride)..((i + 1) * self.stride)]
+    }
+
+    /// Returns the number of bits which are on
+    pub fn count_ones(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    /// Returns the number of bits which are on in row `i`
+    pub fn count_row_ones(&self, i: usize) -> usize {
+        self.row(i).iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    /// Returns the grid with each bit moved `n` columns to the right, or to the
+    /// left if `n` is negative. Bits moved off the grid are lost.
+    pub fn shift_cols(&self, n: isize) -> Self {
+        let mut shifted = BitGrid::new(self.nrow, self.ncol);
+        if n.unsigned_abs() >= self.ncol {
+            return shifted;
+        }
+
+        let (words, bits) = (n.unsigned_abs() / WORD, n.unsigned_abs() % WORD);
+        for i in 0..self.nrow {
+            let (src, dst) = (self.row(i), i * self.stride);
+            for k in 0..self.stride {
+                shifted.words[dst + k] = match n >= 0 {
+                    true => _word(src, k as isize - words as isize) << bits
+                        | _word(src, k as isize - words as isize - 1).checked_shr((WORD - bits) as u32).unwrap_or(0),
+                    false => _word(src, (k + words) as isize) >> bits
+                        | _word(src, (k + words + 1) as isize).checked_shl((WORD - bits) as u32).unwrap_or(0),
+                };
+            }
+        }
+
+        shifted._mask();
+        shifted
+    }
+
+    /// Returns the grid with each row moved `n` rows down, or up if `n` is
+    /// negative. Rows moved off the grid are lost.
+    pub fn shift_rows(&self, n: isize) -> Self {
+        let mut shifted = BitGrid::new(self.nrow, self.ncol);
+        if n.unsigned_abs() >= self.nrow {
+            return shifted;
+        }
+
+        let len = (self.nrow - n.unsigned_abs()) * self.stride;
+        let offset = n.unsigned_abs() * self.stride;
+        match n >= 0 {
+            true => shifted.words[offset..].copy_from_slice(&self.words[..len]),
+            false => shifted.words[..len].copy_from_slice(&self.words[offset..]),
+        }
+
+        shifted
+    }
+
+    /// As `shift_cols`, but bits moved off one side come back on the other
+    pub fn rotate_cols(&self, n: isize) -> Self {
+        if self.ncol == 0 {
+            return self.clone();
+        }
+
+        let n = n.rem_euclid(self.ncol as isize);
+        let mut rotated = self.shift_cols(n);
+        rotated |= &self.shift_cols(n - self.ncol as isize);
+
+        rotated
+    }
+
+    /// As `shift_rows`, but rows moved off one edge come back on the other
+    pub fn rotate_rows(&self, n: isize) -> Self {
+        let mut rotated = self.clone();
+        if self.nrow > 0 {
+            let n = n.rem_euclid(self.nrow as isize) as usize;
+            rotated.words.rotate_right(n * self.stride);
+        }
+
+        rotated
+    }
+
+    /// Returns the grid as text, with `#` for bits which are on and `.` for
+    /// bits which are off
+    pub fn render(&self) -> String {
+        let mut text = String::with_capacity((self.ncol + 1) * self.nrow);
+        for i in 0..self.nrow {
+            if i > 0 {
+                text.push('\n');
+            }
+            text.extend((0..self.ncol).map(|j| if self.get(i, j) { '#' } else { '.' }));
+        }
+
+        text
+    }
+
+    /// Turn off the bits beyond the last column of each row
+    fn _mask(&mut self) {
+        let bits = self.ncol % WORD;
+        if bits > 0 {
+            for row in self.words.chunks_mut(self.stride) {
+                row[self.stride - 1] &= (1 << bits) - 1;
+            }
+        }
+    }
+
+    fn _check_size(&self, other: &BitGrid) {
+        assert!(
+            (self.nrow, self.ncol) == (other.nrow, other.ncol),
+            "grids of size {}x{} and {}x{} can't be combined",
+            self.nrow, self.ncol, other.nrow, other.ncol,
+        );
+    }
+}
+
+/// Returns word `k` of a row, or zero if `k` is outside the row
+fn _word(row: &[u64], k: isize) -> u64 {
+    usize::try_from(k).ok().and_then(|k| row.get(k)).copied().unwrap_or(0)
+}
+
+impl BitAndAssign<&BitGrid> for BitGrid {
+    fn bitand_assign(&mut self, other: &BitGrid) {
+        self._check_size(other);
+        self.words.iter_mut().zip(&other.words).for_each(|(x, y)| *x &= y);
+    }
+}
+
+impl BitOrAssign<&BitGrid> for BitGrid {
+    fn bitor_assign(&mut self, other: &BitGrid) {
+        self._check_size(other);
+        self.words.iter_mut().zip(&other.words).for_each(|(x, y)| *x |= y);
+    }
+}
+
+impl BitAnd for &BitGrid {
+    type Output = BitGrid;
+
+    fn bitand(self, other: &BitGrid) -> BitGrid {
+        let mut grid = self.clone();
+        grid &= other;
+        grid
+    }
+}
+
+impl BitOr for &BitGrid {
+    type Output = BitGrid;
+
+    fn bitor(self, other: &BitGrid) -> BitGrid {
+        let mut grid = self.clone();
+        grid |= other;
+        grid
+    }
+}
+
+impl Not for &BitGrid {
+    type Output = BitGrid;
+
+    fn not(self) -> BitGrid {
+        let mut grid = self.clone();
+        grid.words.iter_mut().for_each(|x| *x = !*x);
+        grid._mask();
+        grid
+    }
+}
+
+impl fmt::Display for BitGrid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.render())
+    }
+}
+
+// Sparse grid -----------------------------------------------------------------
+
+pub type Position = (i64, i64);
+
+/// An unbounded grid, indexed by signed (row, col) positions, in which every
+/// element not set otherwise takes a default value. Only the other elements
+/// are stored, so the grid can grow in any direction without being padded.
+#[derive(Debug, Clone)]
+pub struct SparseGrid<T> {
+    elements: HashMap<Position, T, BuildHasherDefault<PositionHasher>>,
+    default: T,
+    bounds: Cell<Option<(Position, Position)>>,
+    stale: Cell<bool>,
+}
+
+impl<T: Copy + PartialEq> SparseGrid<T> {
+    /// Returns a grid filled with the `default` value
+    pub fn new(default: T) -> Self {
+        SparseGrid {
+            elements: HashMap::default(),
+            default,
+            bounds: Cell::new(None),
+            stale: Cell::new(false),
+        }
+    }
+
+    /// Returns the element at position (i, j)
+    pub fn get(&self, (i, j): Position) -> T {
+        self.elements.get(&(i, j)).copied().unwrap_or(self.default)
+    }
+
+    /// Set the value of the element at position (i, j). Setting it to the
+    /// default value removes it from the grid.
+    pub fn set(&mut self, pos: Position, x: T) {
+        if x != self.default {
+            self.elements.insert(pos, x);
+            if !self.stale.get() {
+                self.bounds.set(Some(match self.bounds.get() {
+                    Some((min, max)) => ((min.0.min(pos.0), min.1.min(pos.1)), (max.0.max(pos.0), max.1.max(pos.1))),
+                    None => (pos, pos),
+                }));
+            }
+        } else if self.elements.remove(&pos).is_some() {
+            // Shrinking the bounds means looking at every element, so wait
+            // until they're asked for
+            if let Some((min, max)) = self.bounds.get() {
+                if pos.0 == min.0 || pos.0 == max.0 || pos.1 == min.1 || pos.1 == max.1 {
+                    self.stale.set(true);
+                }
+            }
+        }
+    }
+
+    /// Returns the number of elements which aren't the default
+    pub fn len(&self) -> usize {
+        self.elements.len()
+    }
+
+    /// Returns true if every element is the default
+    pub fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+
+    /// Returns the top-left and bottom-right corners of the smallest
+    /// rectangle holding every element which isn't the default
+    pub fn bounds(&self) -> Option<(Position, Position)> {
+        if self.stale.replace(false) {
+            let bounds = self.elements.keys().fold(None, |bounds, &(i, j)| match bounds {
+                Some(((i0, j0), (i1, j1))) => Some(((i.min(i0), j.min(j0)), (i.max(i1), j.max(j1)))),
+                None => Some(((i, j), (i, j))),
+            });
+            self.bounds.set(bounds);
+        }
+
+        self.bounds.get()
+    }
+
+    /// Returns an iterator over the position and value of each element which
+    /// isn't the default, in no particular order
+    pub fn iter(&self) -> impl Iterator<Item = (Position, T)> + '_ {
+        self.elements.iter().map(|(&pos, &x)| (pos, x))
+    }
+
+    /// Returns the positions of the four elements orthogonally adjacent to
+    /// (i, j)
+    pub fn neighbours(&self, (i, j): Position) -> [Position; 4] {
+        ORTHOGONAL.map(|(di, dj)| (i + di as i64, j + dj as i64))
+    }
+
+    /// Returns the positions of all eight elements surrounding (i, j),
+    /// including the diagonals
+    pub fn surrounding(&self, (i, j): Position) -> [Position; 8] {
+        SURROUNDING.map(|(di, dj)| (i + di as i64, j + dj as i64))
+    }
+}
+
+/// Draws the rectangle within the bounds, one row per line
+impl<T: Copy + PartialEq + fmt::Display> fmt::Display for SparseGrid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Some(((i0, j0), (i1, j1))) = self.bounds() else {
+            return Ok(());
+        };
+
+        for i in i0..=i1 {
+            if i > i0 {
+                writeln!(f)?;
+            }
+            for j in j0..=j1 {
+                write!(f, "{}", self.get((i, j)))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A hasher for positions, which are too simple to need the protection of
+/// the default SipHash, and which it would otherwise take most of the time
+/// of a lookup to hash. This is the multiply and rotate hash used by rustc.
+#[derive(Default)]
+pub struct PositionHasher {
+    hash: u64,
+}
+
+impl PositionHasher {
+    fn add(&mut self, x: u64) {
+        self.hash = (self.hash.rotate_left(5) ^ x).wrapping_mul(0x517c_c1b7_2722_0a95);
+    }
+}
+
+impl Hasher for PositionHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        bytes.iter().for_each(|&b| self.add(b as u64));
+    }
+
+    fn write_i64(&mut self, x: i64) {
+        self.add(x as u64);
+    }
+
+    fn finish(&self) -> u64 {
+        self.hash
+    }
+}
+
+// N-dimensional grid ----------------------------------------------------------
+
+/// Which cells count as neighbours in a `GridN`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Connectivity {
+    /// Cells sharing a face, which differ along one axis
+    Face,
+    /// Cells sharing a face or an edge, which differ along at most two axes
+    Edge,
+    /// Cells sharing a face, an edge or a corner
+    Corner,
+}
+
+impl Connectivity {
+    /// Returns the most axes along which a neighbour may differ
+    fn max_axes(self, n: usize) -> usize {
+        match self {
+            Connectivity::Face => 1,
+            Connectivity::Edge => 2,
+            Connectivity::Corner => n,
+        }
+    }
+}
+
+/// A dense grid with `N` dimensions, indexed by an array of coordinates with
+/// the last axis varying fastest
+#[derive(Debug, Clone)]
+pub struct GridN<T, const N: usize> {
+    elements: Vec<T>,
+    pub dims: [usize; N],
+}
+
+impl<T: Copy + PartialEq, const N: usize> GridN<T, N> {
+    /// Returns a grid of the given size, filled with `x`
+    pub fn new(dims: [usize; N], x: T) -> Self {
+        GridN { elements: vec![x; dims.iter().product()], dims }
+    }
+
+    /// Returns the element at `coord`
+    pub fn get(&self, coord: [usize; N]) -> T {
+        self.elements[self._as_index(coord)]
+    }
+
+    /// Returns the element at `coord`, or `None` if that lies outside the
+    /// grid
+    pub fn get_checked(&self, coord: [usize; N]) -> Option<T> {
+        coord.iter().zip(self.dims).all(|(&c, d)| c < d).then(|| self.get(coord))
+    }
+
+    /// Set the value of the element at `coord`
+    pub fn set(&mut self, coord: [usize; N], x: T) {
+        let idx = self._as_index(coord);
+        self.elements[idx] = x;
+    }
+
+    /// Returns the number of elements in the grid
+    pub fn len(&self) -> usize {
+        self.elements.len()
+    }
+
+    /// Returns true if the grid has no elements
+    pub fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+
+    /// Returns the coordinates and value of each element, in index order
+    pub fn iter(&self) -> impl Iterator<Item = ([usize; N], T)> + '_ {
+        self.elements.iter().enumerate().map(|(idx, &x)| (self._as_coord(idx), x))
+    }
+
+    /// Returns the coordinates of the neighbours of `coord` which lie within
+    /// the grid
+    pub fn neighbours(&self, coord: [usize; N], connectivity: Connectivity) -> impl Iterator<Item = [usize; N]> {
+        _neighbours(self.dims, coord, connectivity)
+    }
+
+    /// Replaces the element at `start`, and every element of the same value
+    /// connected to it, with `x`. Returns the number of elements replaced.
+    pub fn flood_fill(&mut self, start: [usize; N], connectivity: Connectivity, x: T) -> usize {
+        let target = self.get(start);
+        if target == x {
+            return 0;
+        }
+
+        self.set(start, x);
+        let mut stack = vec![start];
+        let mut count = 1;
+
+        while let Some(coord) = stack.pop() {
+            for next in _neighbours(self.dims, coord, connectivity) {
+                if self.get(next) == target {
+                    self.set(next, x);
+                    stack.push(next);
+                    count += 1;
+                }
+            }
+        }
+
+        count
+    }
+
+    /// Returns a grid labelling each element with the index of its connected
+    /// component, the largest set of connected elements sharing its value,
+    /// along with the number of components. Components are numbered in the
+    /// order of their first element.
+    pub fn components(&self, connectivity: Connectivity) -> (GridN<usize, N>, usize) {
+        let mut labels = GridN::new(self.dims, usize::MAX);
+        let mut count = 0;
+
+        for idx in 0..self.len() {
+            if labels.elements[idx] != usize::MAX {
+                continue;
+            }
+
+            let start = self._as_coord(idx);
+            let value = self.get(start);
+            labels.set(start, count);
+            let mut stack = vec![start];
+
+            while let Some(coord) = stack.pop() {
+                for next in _neighbours(self.dims, coord, connectivity) {
+                    if labels.get(next) == usize::MAX && self.get(next) == value {
+                        labels.set(next, count);
+                        stack.push(next);
+                    }
+                }
+            }
+
+            count += 1;
+        }
+
+        (labels, count)
+    }
+
+    /// Converts coordinates into a position in the vector
+    fn _as_index(&self, coord: [usize; N]) -> usize {
+        coord.iter().zip(self.dims).fold(0, |idx, (&c, d)| idx * d + c)
+    }
+
+    /// Converts a position in the vector into coordinates
+    fn _as_coord(&self, mut idx: usize) -> [usize; N] {
+        let mut coord = [0; N];
+        for k in (0..N).rev() {
+            coord[k] = idx % self.dims[k];
+            idx /= self.dims[k];
+        }
+
+        coord
+    }
+}
+
+/// Returns the neighbours of `coord` within a grid of size `dims`
+fn _neighbours<const N: usize>(
+    dims: [usize; N],
+    coord: [usize; N],
+    connectivity: Connectivity,
+) -> impl Iterator<Item = [usize; N]> {
+    let step = move |mut next: [usize; N], axis: usize, up: bool| {
+        next[axis] = match up {
+            true => Some(next[axis] + 1).filter(|&c| c < dims[axis])?,
+            false => next[axis].checked_sub(1)?,
+        };
+        Some(next)
+    };
+
+    // Face neighbours are by far the most common, so they're found directly.
+    // Otherwise each of the 3^N offsets is numbered in base 3, with digits 0,
+    // 1 and 2 standing for steps of -1, 0 and +1 along each axis.
+    let faces = connectivity == Connectivity::Face;
+    let max_axes = connectivity.max_axes(N);
+    let count = if faces { 2 * N } else { 3usize.pow(N as u32) };
+
+    (0..count).filter_map(move |mut k| {
+        if faces {
+            return step(coord, k / 2, k % 2 == 1);
+        }
+
+        let mut next = coord;
+        let mut axes = 0;
+        for axis in (0..N).rev() {
+            let digit = k % 3;
+            k /= 3;
+
+            if digit != 1 {
+                next = step(next, axis, digit == 2)?;
+                axes += 1;
+            }
+        }
+
+        (1..=max_axes).contains(&axes).then_some(next)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grid_iterators() {
+        let grid = Grid::parse("abc\ndef\nghi").unwrap();
+
+        assert_eq!(grid.neighbours(0, 0).collect::<Vec<_>>(), [(1, 0), (0, 1)]);
+        assert_eq!(grid.surrounding(1, 1).count(), 8);
+        assert_eq!(grid.ray(0, 0, (1, 1)).collect::<Vec<_>>(), [(1, 1), (2, 2)]);
+        assert_eq!(grid.ray(2, 1, (-1, 0)).map(|(i, j)| grid.get(i, j)).collect::<Vec<_>>(), b"eb");
+        assert_eq!(grid.row(1), b"def");
+        assert_eq!(grid.column(2).rev().collect::<Vec<_>>(), b"ifc");
+        assert_eq!(grid.get_checked(3, 0), None);
+        assert_eq!(grid.get_offset(1, 1, (-1, 1)), Some(b'c'));
+        assert_eq!(grid.get_offset(0, 1, (-1, 0)), None);
+        assert_eq!(grid.iter().nth(5), Some(((1, 2), b'f')));
+    }
+
+    #[test]
+    fn grid_transformations() {
+        let grid = Grid::parse("abc\ndef").unwrap();
+        let text = |g: &Grid<u8>| g.to_string();
+
+        assert_eq!(text(&grid.transpose()), "ad\nbe\ncf");
+        assert_eq!(text(&grid.rotate_clockwise()), "da\neb\nfc");
+        assert_eq!(text(&grid.rotate_anticlockwise()), "cf\nbe\nad");
+        assert_eq!(text(&grid.flip_horizontal()), "cba\nfed");
+        assert_eq!(text(&grid.flip_vertical()), "def\nabc");
+        assert_eq!(text(&grid.rotate_clockwise().rotate_anticlockwise()), "abc\ndef");
+    }
+
+    #[test]
+    fn grid_views_and_tiles() {
+        let grid = Grid::parse("abcde\nfghij\nklmno").unwrap();
+
+        let view = grid.view(1, 2, 2, 2);
+        assert_eq!(view.get(0, 0), b'h');
+        assert_eq!(view.get_checked(1, 1), Some(b'n'));
+        assert_eq!(view.get_checked(2, 0), None);
+        assert_eq!(view.to_grid().row(1), b"mn");
+
+        let tiles: Vec<_> = grid.tiles(2, 2).collect();
+        assert_eq!(tiles.len(), 6);
+        assert_eq!((tiles[2].top, tiles[2].left, tiles[2].nrow, tiles[2].ncol), (0, 4, 2, 1));
+        assert_eq!(tiles[3].to_grid().row(0), b"kl");
+    }
+
+    #[test]
+    fn grid_parsing() {
+        let grid = Grid::parse_with("#.\n.#", |c| c == '#').unwrap();
+        assert_eq!((grid.get(0, 0), grid.get(0, 1), grid.get(1, 1)), (true, false, true));
+        assert_eq!(grid.render_with(|x| if x { 'X' } else { ' ' }), "X \n X");
+
+        let error = Grid::parse("abc\nde\nfgh").unwrap_err();
+        assert_eq!((error.line, error.column, error.expected.as_str()), (2, 1, "a row of 3 cells"));
+        assert!(Grid::parse("").is_err());
+
+        let padded = Grid::parse_padded("  ab\ncd\n", ' ', |c| c).unwrap();
+        assert_eq!((padded.nrow, padded.ncol), (2, 4));
+        assert_eq!(padded.to_string(), "  ab\ncd  ");
+    }
+
+    #[test]
+    fn bit_grid_operations() {
+        let grid = BitGrid::from_fn(3, 130, |i, j| (i + j) % 3 == 0);
+        assert_eq!((grid.count_ones(), grid.count_row_ones(0)), (130, 44));
+        assert_eq!(grid.row(0).len(), 3);
+
+        // Shifts cross word boundaries and drop whatever falls off the edge
+        let right = grid.shift_cols(65);
+        assert!(right.get(0, 65) && !right.get(0, 66) && right.get(0, 128) && !right.get(0, 0));
+        assert_eq!(right.shift_cols(-65), BitGrid::from_fn(3, 130, |i, j| j < 65 && (i + j) % 3 == 0));
+        assert_eq!(grid.shift_rows(1).row(1), grid.row(0));
+        assert_eq!(grid.shift_rows(-3).count_ones(), 0);
+
+        // Rotations wrap around
+        assert_eq!(grid.rotate_cols(130), grid);
+        assert_eq!(grid.rotate_cols(-1).rotate_cols(1), grid);
+        assert!(grid.rotate_cols(1).get(0, 0) == grid.get(0, 129));
+        assert_eq!(grid.rotate_rows(2).rotate_rows(1), grid);
+
+        // Not leaves the bits beyond the last column off
+        let not = !&grid;
+        assert_eq!(not.count_ones(), 3 * 130 - 130);
+        assert_eq!((&not & &grid).count_ones(), 0);
+        assert_eq!((&not | &grid).count_ones(), 3 * 130);
+
+        let small = BitGrid::from_fn(2, 3, |i, j| i == j);
+        assert_eq!(small.to_string(), "#..\n.#.");
+    }
+
+    #[test]
+    fn grid_n_neighbourhoods() {
+        let grid = GridN::new([3, 3, 3], 0);
+
+        assert_eq!(grid.neighbours([1, 1, 1], Connectivity::Face).count(), 6);
+        assert_eq!(grid.neighbours([1, 1, 1], Connectivity::Edge).count(), 18);
+        assert_eq!(grid.neighbours([1, 1, 1], Connectivity::Corner).count(), 26);
+        assert_eq!(grid.neighbours([0, 0, 0], Connectivity::Corner).count(), 7);
+
+        let mut edge: Vec<_> = grid.neighbours([0, 2, 1], Connectivity::Face).collect();
+        edge.sort();
+        assert_eq!(edge, [[0, 1, 1], [0, 2, 0], [0, 2, 2], [1, 2, 1]]);
+    }
+
+    /// A flood fill through a large open grid would overflow the stack if it
+    /// recursed
+    #[test]
+    fn grid_n_flood_fill_and_components() {
+        let mut grid = GridN::new([40, 40, 40], 0);
+        for i in 0..40 {
+            for j in 0..40 {
+                grid.set([20, i, j], 1); // A wall cutting the grid in two
+            }
+        }
+
+        assert_eq!(grid.components(Connectivity::Face).1, 3);
+        assert_eq!(grid.flood_fill([0, 0, 0], Connectivity::Face, 2), 20 * 40 * 40);
+        assert_eq!(grid.get([19, 39, 39]), 2);
+        assert_eq!(grid.get([21, 0, 0]), 0);
+    }
+
+    #[test]
+    fn sparse_grid_bounds() {
+        let mut grid = SparseGrid::new('.');
+        assert_eq!(grid.bounds(), None);
+
+        grid.set((-2, 3), '#');
+        grid.set((1, -1), '#');
+        grid.set((0, 0), '#');
+        assert_eq!(grid.bounds(), Some(((-2, -1), (1, 3))));
+        assert_eq!(grid.to_string(), "....#\n.....\n.#...\n#....");
+
+        // Removing an element on the edge shrinks the bounds
+        grid.set((-2, 3), '.');
+        assert_eq!(grid.bounds(), Some(((0, -1), (1, 0))));
+        assert_eq!(grid.len(), 2);
+    }
+}
@@ -0,0 +1,348 @@
+//! A history of benchmark results, for spotting performance regressions
+//!
+//! Each saved run appends a JSON record for every part it solved to
+//! `history.jsonl` in the bench directory, stamped with the time of the run.
+//! New runs are compared against the most recent record for each day and
+//! part, so a run of a single day can be compared just as well as a run of
+//! them all.
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::json::Value;
+use crate::output;
+use crate::solution::Report;
+
+pub const HISTORY_FILE: &str = "history.jsonl";
+
+/// A timed step of a solution
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Step {
+    Parse,
+    Part(u8),
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Step::Parse => write!(f, "parse"),
+            Step::Part(p) => write!(f, "part {}", p),
+        }
+    }
+}
+
+/// The median time taken by each step of each day, along with when it was
+/// recorded
+#[derive(Debug, Default, PartialEq)]
+pub struct Timings {
+    medians: BTreeMap<(u8, Step), (u64, Duration)>,
+}
+
+impl Timings {
+    /// Returns the timings from the reports of a run made at `timestamp`
+    pub fn new(timestamp: u64, reports: &[(u8, Report)]) -> Self {
+        let mut timings = Timings::default();
+
+        for (day, report) in reports {
+            timings.insert(*day, Step::Parse, timestamp, report.parse.median);
+            for answer in &report.answers {
+                timings.insert(*day, Step::Part(answer.part), timestamp, answer.elapsed.median);
+            }
+        }
+
+        timings
+    }
+
+    /// Records a timing, unless there's a more recent one for the same step
+    fn insert(&mut self, day: u8, step: Step, timestamp: u64, median: Duration) {
+        let entry = self.medians.entry((day, step)).or_insert((timestamp, median));
+        if timestamp >= entry.0 {
+            *entry = (timestamp, median);
+        }
+    }
+
+    /// Returns these timings updated with any more recent ones from `other`
+    pub fn merge(mut self, other: &Timings) -> Self {
+        for (&(day, step), &(timestamp, median)) in &other.medians {
+            self.insert(day, step, timestamp, median);
+        }
+
+        self
+    }
+
+    /// Returns the total of the median timings for each day
+    pub fn totals(&self) -> BTreeMap<u8, Duration> {
+        let mut totals = BTreeMap::new();
+        for (&(day, _), &(_, median)) in &self.medians {
+            *totals.entry(day).or_insert(Duration::ZERO) += median;
+        }
+
+        totals
+    }
+}
+
+/// Returns the path of the history file in the given bench directory
+pub fn path(dir: &Path) -> PathBuf {
+    dir.join(HISTORY_FILE)
+}
+
+/// Appends the results of a run made at `timestamp` to the history
+pub fn save(dir: &Path, timestamp: u64, reports: &[(u8, Report)]) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+    let mut file = OpenOptions::new().create(true).append(true).open(path(dir))?;
+
+    for (day, report) in reports {
+        for answer in &report.answers {
+            writeln!(file, "{}", output::json_record(*day, report, answer, Some(timestamp)))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the most recent timing of each step recorded in the history. The
+/// history is empty if it hasn't been saved to yet.
+pub fn load(dir: &Path) -> Result<Timings, Box<dyn Error>> {
+    let path = path(dir);
+    let history = match fs::read_to_string(&path) {
+        Ok(history) => history,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Timings::default()),
+        Err(e) => return Err(format!("{}: {}", path.display(), e).into()),
+    };
+
+    let mut timings = Timings::default();
+    for (i, line) in history.lines().enumerate().filter(|(_, l)| !l.trim().is_empty()) {
+        let record = Value::parse(line).map_err(|mut e| {
+            e.line = i + 1;
+            format!("{}: {}", path.display(), e)
+        })?;
+
+        let field = |keys: &[&str]| -> Result<f64, String> {
+            keys.iter()
+                .try_fold(&record, |value, key| value.get(key))
+                .and_then(Value::as_f64)
+                .ok_or_else(|| format!("{}: line {}: no number at `{}`", path.display(), i + 1, keys.join(".")))
+        };
+
+        let timestamp = field(&["timestamp"])? as u64;
+        let day = field(&["day"])? as u8;
+        let part = field(&["part"])? as u8;
+        let nanos = |x: f64| Duration::from_nanos(x as u64);
+
+        timings.insert(day, Step::Parse, timestamp, nanos(field(&["parse_ns", "median"])?));
+        timings.insert(day, Step::Part(part), timestamp, nanos(field(&["solve_ns", "median"])?));
+    }
+
+    Ok(timings)
+}
+
+// Regressions -----------------------------------------------------------------
+
+/// A step which took noticeably longer than it did before
+#[derive(Debug)]
+pub struct Regression {
+    pub day: u8,
+    pub step: Step,
+    pub before: Duration,
+    pub after: Duration,
+}
+
+impl Regression {
+    /// Returns the increase in time taken, as a percentage
+    pub fn increase(&self) -> f64 {
+        100.0 * (self.after.as_secs_f64() / self.before.as_secs_f64() - 1.0)
+    }
+}
+
+impl fmt::Display for Regression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "day {:02}, {}: {:.1}µs -> {:.1}µs (+{:.1}%)",
+            self.day,
+            self.step,
+            self.before.as_secs_f64() * 1e6,
+            self.after.as_secs_f64() * 1e6,
+            self.increase(),
+        )
+    }
+}
+
+/// The outcome of comparing a run with the history
+#[derive(Debug)]
+pub struct Comparison {
+    /// The number of steps which had been timed before
+    pub compared: usize,
+    pub regressions: Vec<Regression>,
+}
+
+/// Returns the steps which took more than `threshold` percent longer in the
+/// current run than in the baseline. Steps missing from the baseline are
+/// skipped. The times are compared without dividing, so a step exactly at the
+/// threshold isn't flagged because of rounding.
+pub fn compare(baseline: &Timings, current: &Timings, threshold: f64) -> Comparison {
+    let mut comparison = Comparison { compared: 0, regressions: Vec::new() };
+
+    for (&(day, step), &(_, after)) in &current.medians {
+        if let Some(&(_, before)) = baseline.medians.get(&(day, step)) {
+            comparison.compared += 1;
+
+            let (b, a) = (before.as_nanos() as f64, after.as_nanos() as f64);
+            if before > Duration::ZERO && 100.0 * a > (100.0 + threshold) * b {
+                comparison.regressions.push(Regression { day, step, before, after });
+            }
+        }
+    }
+
+    comparison
+}
+
+// README chart ----------------------------------------------------------------
+
+/// Returns the README with the axis and bars of its `xychart-beta` block
+/// replaced by the total time, in microseconds, taken by each day. The rest
+/// of the chart, such as its title and the range of the y-axis, is left as is.
+pub fn update_chart(readme: &str, timings: &Timings) -> Result<String, &'static str> {
+    let totals = timings.totals();
+    let join = |values: Vec<String>| values.join(", ");
+    let days = join(totals.keys().map(|d| d.to_string()).collect());
+    let bars = join(totals.values().map(|t| t.as_micros().to_string()).collect());
+
+    let mut lines: Vec<String> = Vec::new();
+    let mut in_chart = false;
+    let mut found = false;
+
+    for line in readme.lines() {
+        let trimmed = line.trim_start();
+        let indent = &line[..(line.len() - trimmed.len())];
+
+        if trimmed.starts_with("xychart-beta") {
+            in_chart = true;
+            found = true;
+        } else if trimmed.starts_with("```") {
+            in_chart = false;
+        } else if in_chart && trimmed.starts_with("x-axis") {
+            lines.push(format!("{}x-axis [{}]", indent, days));
+            continue;
+        } else if in_chart && trimmed.starts_with("bar") {
+            lines.push(format!("{}bar [{}]", indent, bars));
+            continue;
+        }
+
+        lines.push(line.to_string());
+    }
+
+    if !found {
+        return Err("the README has no xychart-beta block to update");
+    }
+
+    let mut readme_out = lines.join("\n");
+    if readme.ends_with('\n') {
+        readme_out.push('\n');
+    }
+
+    Ok(readme_out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bench::Stats;
+    use crate::solution::Answer;
+
+    /// Returns a report whose steps each took the given number of microseconds
+    fn report(parse: u64, parts: &[u64]) -> Report {
+        let stats = |micros| Stats::new(&mut [Duration::from_micros(micros)]);
+        let answers = parts
+            .iter()
+            .enumerate()
+            .map(|(i, &t)| Answer { part: i as u8 + 1, value: "1".to_string(), elapsed: stats(t) })
+            .collect();
+
+        Report { parse: stats(parse), answers }
+    }
+
+    /// Returns an empty directory to keep a history in
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("aoc2022-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn regressions() {
+        let baseline = Timings::new(1, &[(1, report(100, &[1000, 20]))]);
+        let current = Timings::new(2, &[(1, report(110, &[1001, 20])), (2, report(5, &[5, 5]))]);
+
+        // Parsing is exactly 10% slower, part 1 just over 0.1% and part 2 the same
+        let comparison = compare(&baseline, &current, 10.0);
+        assert_eq!(comparison.compared, 3);
+        assert!(comparison.regressions.is_empty());
+
+        let comparison = compare(&baseline, &current, 0.1);
+        let regressed: Vec<_> = comparison.regressions.iter().map(|r| r.step).collect();
+        assert_eq!(regressed, [Step::Parse]);
+
+        let current = Timings::new(2, &[(1, report(110, &[1002, 20]))]);
+        let comparison = compare(&baseline, &current, 0.1);
+        let regressed: Vec<_> = comparison.regressions.iter().map(|r| r.step).collect();
+        assert_eq!(regressed, [Step::Parse, Step::Part(1)]);
+        assert_eq!(comparison.regressions[0].to_string(), "day 01, parse: 100.0µs -> 110.0µs (+10.0%)");
+    }
+
+    #[test]
+    fn save_and_load() {
+        let dir = scratch("history");
+        assert_eq!(load(&dir).unwrap(), Timings::default());
+
+        let (first, second) = ([(1, report(10, &[20, 30]))], [(1, report(15, &[25]))]);
+        save(&dir, 100, &first).unwrap();
+        save(&dir, 200, &second).unwrap();
+
+        // The latest timing of each step wins
+        let loaded = load(&dir).unwrap();
+        assert_eq!(loaded, Timings::new(100, &first).merge(&Timings::new(200, &second)));
+        assert_eq!(loaded.totals()[&1], Duration::from_micros(15 + 25 + 30));
+
+        fs::write(path(&dir), "{\"timestamp\": 1, \"day\": 1, \"part\": 1}\n").unwrap();
+        let error = load(&dir).unwrap_err().to_string();
+        assert!(error.ends_with("line 1: no number at `parse_ns.median`"), "{}", error);
+
+        fs::write(path(&dir), "\n{\"day\": 1,\n").unwrap();
+        let error = load(&dir).unwrap_err().to_string();
+        assert!(error.contains("line 2"), "{}", error);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn chart() {
+        let readme = "\
+# Timings
+
+```mermaid
+  xychart-beta
+    title \"Time per day\"
+    x-axis [1, 2]
+    y-axis \"µs\" 0 --> 100
+    bar [50, 60]
+```
+
+A `bar [1]` outside the chart stays as it is.
+";
+        let timings = Timings::new(1, &[(1, report(10, &[20, 30])), (3, report(1, &[2, 3]))]);
+        let updated = update_chart(readme, &timings).unwrap();
+
+        let expected = readme
+            .replace("x-axis [1, 2]", "x-axis [1, 3]")
+            .replace("bar [50, 60]", "bar [60, 6]");
+        assert_eq!(updated, expected);
+        assert_eq!(update_chart(readme.trim_end(), &timings).unwrap(), expected.trim_end());
+        assert!(update_chart("# No chart\n", &timings).is_err());
+    }
+}
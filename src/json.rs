@@ -0,0 +1,238 @@
+//! Just enough JSON to read back the records we write
+//!
+//! Numbers are held as `f64`, which is exact for integers up to 2^53. That's
+//! plenty for nanosecond timings and timestamps.
+
+use crate::error::ParseError;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    /// Returns the value described by a JSON document
+    pub fn parse(input: &str) -> Result<Value, ParseError> {
+        let mut parser = Parser { input, pos: 0 };
+
+        let value = parser.value()?;
+        parser.skip_whitespace();
+        if parser.pos < input.len() {
+            return Err(ParseError::at_byte(input, parser.pos, "the end of the document"));
+        }
+
+        Ok(value)
+    }
+
+    /// Returns the value of a field, if this is an object which has it
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Object(fields) => fields.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Number(x) => Some(*x),
+            _ => None,
+        }
+    }
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn skip_whitespace(&mut self) {
+        let rest = &self.input[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.as_bytes().get(self.pos).copied()
+    }
+
+    fn error(&self, expected: &str) -> ParseError {
+        ParseError::at_byte(self.input, self.pos, expected)
+    }
+
+    /// Consumes `token` if it comes next
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_whitespace();
+        if self.input[self.pos..].starts_with(token) {
+            self.pos += token.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: &str) -> Result<(), ParseError> {
+        match self.eat(token) {
+            true => Ok(()),
+            false => Err(self.error(&format!("`{}`", token))),
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, ParseError> {
+        self.skip_whitespace();
+
+        match self.peek() {
+            Some(b'{') => self.object(),
+            Some(b'[') => self.array(),
+            Some(b'"') => Ok(Value::String(self.string()?)),
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            _ if self.eat("true") => Ok(Value::Bool(true)),
+            _ if self.eat("false") => Ok(Value::Bool(false)),
+            _ if self.eat("null") => Ok(Value::Null),
+            _ => Err(self.error("a JSON value")),
+        }
+    }
+
+    fn object(&mut self) -> Result<Value, ParseError> {
+        self.expect("{")?;
+
+        let mut fields = Vec::new();
+        if self.eat("}") {
+            return Ok(Value::Object(fields));
+        }
+
+        loop {
+            self.skip_whitespace();
+            if self.peek() != Some(b'"') {
+                return Err(self.error("a field name"));
+            }
+            let key = self.string()?;
+            self.expect(":")?;
+            fields.push((key, self.value()?));
+
+            if self.eat("}") {
+                return Ok(Value::Object(fields));
+            }
+            self.expect(",")?;
+        }
+    }
+
+    fn array(&mut self) -> Result<Value, ParseError> {
+        self.expect("[")?;
+
+        let mut values = Vec::new();
+        if self.eat("]") {
+            return Ok(Value::Array(values));
+        }
+
+        loop {
+            values.push(self.value()?);
+
+            if self.eat("]") {
+                return Ok(Value::Array(values));
+            }
+            self.expect(",")?;
+        }
+    }
+
+    fn number(&mut self) -> Result<Value, ParseError> {
+        let rest = &self.input[self.pos..];
+        let len = rest
+            .find(|c: char| !(c.is_ascii_digit() || "+-.eE".contains(c)))
+            .unwrap_or(rest.len());
+        let token = &rest[..len];
+
+        let x = token
+            .parse()
+            .map_err(|_| ParseError::at(self.input, token, "a number"))?;
+        self.pos += len;
+
+        Ok(Value::Number(x))
+    }
+
+    fn string(&mut self) -> Result<String, ParseError> {
+        self.pos += 1; // Opening quote
+
+        let mut s = String::new();
+        let mut chars = self.input[self.pos..].char_indices();
+
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.pos += i + 1;
+                    return Ok(s);
+                },
+                '\\' => {
+                    let escaped = match chars.next() {
+                        Some((_, '"')) => '"',
+                        Some((_, '\\')) => '\\',
+                        Some((_, '/')) => '/',
+                        Some((_, 'n')) => '\n',
+                        Some((_, 'r')) => '\r',
+                        Some((_, 't')) => '\t',
+                        Some((_, 'b')) => '\u{8}',
+                        Some((_, 'f')) => '\u{c}',
+                        Some((j, 'u')) => {
+                            let start = self.pos + j + 1;
+                            let hex = self.input.get(start..start + 4).unwrap_or("");
+                            let c = u32::from_str_radix(hex, 16).ok().and_then(char::from_u32);
+                            chars.nth(3);
+                            c.ok_or_else(|| ParseError::at_byte(self.input, start, "four hex digits"))?
+                        },
+                        _ => return Err(ParseError::at_byte(self.input, self.pos + i + 1, "an escape")),
+                    };
+                    s.push(escaped);
+                },
+                c => s.push(c),
+            }
+        }
+
+        Err(ParseError::eof(self.input, "a closing `\"`"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_values() {
+        let value = Value::parse(r#" {"day": 1, "ns": [1.5e3, -2], "ok": true, "no": false, "none": null,
+            "text": "a\"b\\c\/\n\u00e9", "empty": {}, "nothing": []} "#).unwrap();
+
+        assert_eq!(value.get("day").and_then(Value::as_f64), Some(1.0));
+        assert_eq!(value.get("ns"), Some(&Value::Array(vec![Value::Number(1500.0), Value::Number(-2.0)])));
+        assert_eq!(value.get("ok"), Some(&Value::Bool(true)));
+        assert_eq!(value.get("no"), Some(&Value::Bool(false)));
+        assert_eq!(value.get("none"), Some(&Value::Null));
+        assert_eq!(value.get("text"), Some(&Value::String("a\"b\\c/\né".to_string())));
+        assert_eq!(value.get("empty"), Some(&Value::Object(Vec::new())));
+        assert_eq!(value.get("nothing"), Some(&Value::Array(Vec::new())));
+        assert_eq!(value.get("missing"), None);
+        assert_eq!(Value::Null.get("day"), None);
+    }
+
+    #[test]
+    fn parse_errors() {
+        let errors = [
+            ("", 1, "a JSON value"),
+            ("{\"a\": 1} x", 10, "the end of the document"),
+            ("{\"a\" 1}", 6, "`:`"),
+            ("{a: 1}", 2, "a field name"),
+            ("[1 2]", 4, "`,`"),
+            ("1.2.3", 1, "a number"),
+            ("\"a\\x\"", 4, "an escape"),
+            ("\"\\u12\"", 4, "four hex digits"),
+            ("\"abc", 5, "a closing `\"`"),
+        ];
+
+        for (input, column, expected) in errors {
+            let error = Value::parse(input).unwrap_err();
+            assert_eq!((error.column, error.expected.as_str()), (column, expected), "{}", input);
+        }
+    }
+}
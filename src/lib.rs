@@ -1,105 +1,213 @@
+mod answers;
+pub mod bench;
 pub mod config;
-mod grid;
+pub mod cpu;
+mod error;
+pub mod grid;
+mod history;
+mod json;
+mod ocr;
 mod parse;
+mod picture;
 mod days;
+pub mod search;
+pub mod output;
+mod solution;
 
 use std::error::Error;
-use std::time::Instant;
-
-use crate::config::Config;
-use crate::days::{
-    day01, day02, day03, day04, day05,
-    day06, day07, day08, day09, day10,
-    day11, day12, day13, day14, day15,
-    day16, day17, day18, day19, day20,
-    day21, day22, day23, day24, day25
-};
-    
+use std::fs::read_to_string;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::answers::{Answers, Outcome};
+use crate::bench::Bench;
+use crate::config::{Config, Render, Selection, Source};
+use crate::days::Day;
+use crate::history::Timings;
+use crate::solution::Report;
+
 // Run the code specified by the CLI inputs
 pub fn run(config: Config) -> Result<(), Box<dyn Error>> {
-    let clock = Instant::now();
-
-    match config.part {
-        Some(p) => {
-            println!("\nRunning day {:02}, part {}...", config.day, p);
-            let soln = run_part(config.day, p);
-            println!("  - Solution: {}", soln);
-        },
-        None => {
-            println!("\nRunning day {:02}...", config.day);
-            let soln1 = run_part(config.day, 1);
-            let soln2 = run_part(config.day, 2);
-            println!("  - Part one: {}", soln1);
-            println!("  - Part two: {}", soln2);
+    match config.selection {
+        Selection::Day(day) => run_day(&config, day),
+        Selection::All => run_all(&config),
+    }
+}
+
+/// Runs a single day, printing the answers and how long each step took
+fn run_day(config: &Config, day: u8) -> Result<(), Box<dyn Error>> {
+    let day = days::get(day).ok_or("day has not been solved yet")?;
+    let input = read_input(&config.source, day.number)?;
+    let bench = config.bench.unwrap_or(Bench::ONCE);
+
+    let parts = match config.part {
+        Some(p) => vec![p],
+        None => vec![1, 2],
+    };
+
+    let report = match day.solve(&input, &config.params, &parts, &bench) {
+        Ok(report) => report,
+        Err(e) => {
+            // The picture may show why a part couldn't be solved
+            if let Some(render) = &config.render {
+                draw(config, day, &input, &parts, render)?;
+            }
+            return Err(e);
         }
+    };
+    output::print_day(config.format, day.number, &report, config.bench.as_ref());
+
+    if let Some(render) = &config.render {
+        draw(config, day, &input, &parts, render)?;
     }
 
-    let elapsed = clock.elapsed().as_micros();
-    println!("  - Elapsed: {}µs", elapsed);
+    let reports = [(day.number, report)];
+    if let Some(path) = &config.check {
+        check_answers(config, path, &reports)?;
+    }
+    track(config, &reports)
+}
 
-    Ok(())
+/// Runs every registered day, then prints a summary of the results
+fn run_all(config: &Config) -> Result<(), Box<dyn Error>> {
+    let bench = config.bench.unwrap_or(Bench::ONCE);
+    let mut reports = Vec::new();
+
+    for day in days::all() {
+        let input = read_input(&config.source, day.number)?;
+        reports.push((day.number, day.solve(&input, &[], &[1, 2], &bench)?));
+    }
+
+    output::print_all(config.format, &reports, config.bench.as_ref());
+
+    if let Some(path) = &config.check {
+        check_answers(config, path, &reports)?;
+    }
+    track(config, &reports)
 }
 
-fn run_part(day: u8, part: u8) -> String {
-    if part == 1 {
-        match day {
-            1 =>  day01::part1().to_string(),
-            2 =>  day02::part1().to_string(),
-            3 =>  day03::part1().to_string(),
-            4 =>  day04::part1().to_string(),
-            5 =>  day05::part1().to_string(),
-            6 =>  day06::part1().to_string(),
-            7 =>  day07::part1().to_string(),
-            8 =>  day08::part1().to_string(),
-            9 =>  day09::part1().to_string(),
-            10 => day10::part1().to_string(),
-            11 => day11::part1().to_string(),
-            12 => day12::part1().to_string(),
-            13 => day13::part1().to_string(),
-            14 => day14::part1().to_string(),
-            15 => day15::part1().to_string(),
-            16 => day16::part1().to_string(),
-            17 => day17::part1().to_string(),
-            18 => day18::part1().to_string(),
-            19 => day19::part1().to_string(),
-            20 => day20::part1().to_string(),
-            21 => day21::part1().to_string(),
-            22 => day22::part1().to_string(),
-            23 => day23::part1().to_string(),
-            24 => day24::part1().to_string(),
-            25 => day25::part1().to_string(),
-            _  => unimplemented!(),
+/// Draws a picture of how each part was solved, printing it or writing it to
+/// an image file as asked. Each part gets its own file when both are drawn.
+fn draw(config: &Config, day: &Day, input: &str, parts: &[u8], render: &Render) -> Result<(), Box<dyn Error>> {
+    // Some days only draw one of their parts
+    let mut pictures = Vec::new();
+    for &part in parts {
+        if let Some(picture) = day.render(input, &config.params, part)? {
+            pictures.push((part, picture));
         }
-    } else if part == 2 {
-        match day {
-            1 =>  day01::part2().to_string(),
-            2 =>  day02::part2().to_string(),
-            3 =>  day03::part2().to_string(),
-            4 =>  day04::part2().to_string(),
-            5 =>  day05::part2().to_string(),
-            6 =>  day06::part2().to_string(),
-            7 =>  day07::part2().to_string(),
-            8 =>  day08::part2().to_string(),
-            9 =>  day09::part2().to_string(),
-            10 => day10::part2().to_string(),
-            11 => day11::part2().to_string(),
-            12 => day12::part2().to_string(),
-            13 => day13::part2().to_string(),
-            14 => day14::part2().to_string(),
-            15 => day15::part2().to_string(),
-            16 => day16::part2().to_string(),
-            17 => day17::part2().to_string(),
-            18 => day18::part2().to_string(),
-            19 => day19::part2().to_string(),
-            20 => day20::part2().to_string(),
-            21 => day21::part2().to_string(),
-            22 => day22::part2().to_string(),
-            23 => day23::part2().to_string(),
-            24 => day24::part2().to_string(),
-            25 => day25::part2().to_string(),
-            _  => unimplemented!(),
+    }
+
+    if pictures.is_empty() {
+        let parts = parts.iter().map(|p| p.to_string()).collect::<Vec<_>>().join(" or ");
+        return Err(format!("day {:02} can't draw part {}", day.number, parts).into());
+    }
+
+    for (part, picture) in &pictures {
+        match render {
+            Render::Text => output::print_picture(config.format, *part, picture),
+            Render::Image(path) => {
+                let path = match pictures.len() {
+                    1 => path.clone(),
+                    _ => _part_path(path, *part),
+                };
+                std::fs::write(&path, picture.ppm()).map_err(|e| format!("{}: {}", path.display(), e))?;
+            },
         }
-    } else {
-        panic!("part {} undefined", part)
     }
+
+    Ok(())
+}
+
+/// Returns the path with the part number added to the end of its file stem,
+/// so `route.ppm` becomes `route-part1.ppm`
+fn _part_path(path: &Path, part: u8) -> PathBuf {
+    let mut name = path.file_stem().unwrap_or_default().to_os_string();
+    name.push(format!("-part{}", part));
+    if let Some(extension) = path.extension() {
+        name.push(".");
+        name.push(extension);
+    }
+
+    path.with_file_name(name)
+}
+
+/// Compares the answers in each report with those in the answers file,
+/// returning an error if any of them are wrong
+fn check_answers(config: &Config, path: &Path, reports: &[(u8, Report)]) -> Result<(), Box<dyn Error>> {
+    let answers = Answers::parse(&read_file(path)?)
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+
+    let checks: Vec<_> = reports
+        .iter()
+        .flat_map(|(day, report)| answers.check(*day, report))
+        .collect();
+    output::print_checks(config.format, &checks);
+
+    let wrong = checks
+        .iter()
+        .filter(|c| matches!(c.outcome, Outcome::Wrong { .. }))
+        .count();
+    if wrong > 0 {
+        return Err(format!("{} of {} answers were wrong", wrong, checks.len()).into());
+    }
+
+    Ok(())
+}
+
+/// Compares the timings with the benchmark history, saves them to it and
+/// updates the README chart, as asked. Returns an error if any step was
+/// slower than the threshold allows.
+fn track(config: &Config, reports: &[(u8, Report)]) -> Result<(), Box<dyn Error>> {
+    let options = &config.history;
+    if !(options.save || options.update_readme || options.threshold.is_some()) {
+        return Ok(());
+    }
+
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let current = Timings::new(timestamp, reports);
+    let previous = history::load(&options.dir)?;
+
+    let mut regressed = 0;
+    if let Some(threshold) = options.threshold {
+        let comparison = history::compare(&previous, &current, threshold);
+        output::print_regressions(config.format, &comparison, threshold);
+        regressed = comparison.regressions.len();
+    }
+
+    if options.save {
+        history::save(&options.dir, timestamp, reports)
+            .map_err(|e| format!("{}: {}", history::path(&options.dir).display(), e))?;
+    }
+
+    if options.update_readme {
+        let path = Path::new("README.md");
+        let readme = history::update_chart(&read_file(path)?, &previous.merge(&current))?;
+        std::fs::write(path, readme).map_err(|e| format!("{}: {}", path.display(), e))?;
+    }
+
+    if regressed > 0 {
+        return Err(format!("{} steps regressed", regressed).into());
+    }
+
+    Ok(())
+}
+
+/// Returns the puzzle input for the given day
+fn read_input(source: &Source, day: u8) -> io::Result<String> {
+    match source {
+        Source::DataDir(dir) => read_file(&dir.join(format!("day{:02}.txt", day))),
+        Source::File(path) => read_file(path),
+        Source::Stdin => {
+            let mut input = String::new();
+            io::stdin().read_to_string(&mut input)?;
+            Ok(input)
+        }
+    }
+}
+
+/// Reads a file, naming it in the error if it can't be read
+fn read_file(path: &Path) -> io::Result<String> {
+    read_to_string(path)
+        .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))
 }
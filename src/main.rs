@@ -3,7 +3,32 @@
 // To run day `d` simply write `cargo run -- d` at the prompt. 
 // For example, `cargo run -- 5`. You can also choose to run a
 // single part of the chosen day, e.g. `cargo run -- 5 2` will
-// run the solution for day 5, part 2.
+// run the solution for day 5, part 2. Use `cargo run -- all` to run every
+// day and print a summary table.
+//
+// By default the input is read from `data/dayNN.txt`. Pass `--data-dir <dir>`
+// to look in another directory, or `--input <path>` to read a specific file.
+// Use `--input -` to read the input from stdin.
+//
+// Pass `--bench` to time each step over repeated runs, with `--runs <n>` and
+// `--warmup <n>` to control how many.
+//
+// Results are printed as text unless `--format json` or `--format csv` asks
+// for machine-readable records.
+//
+// Pass `--check` to compare the answers with those in `answers.toml` in the
+// data directory, or `--answers <path>` to use a different file.
+//
+// Pass `--save` to keep the timings in `bench/history.jsonl`, `--compare` or
+// `--threshold <pct>` to flag steps which got slower than the saved timings,
+// and `--update-readme` to redraw the README's chart from them. The history
+// lives in another directory with `--bench-dir <dir>`.
+//
+// Pass `--render` to print a picture of how a day was solved, for the days
+// which draw one, or `--render-to <path>` to write it to a PPM image.
+//
+// Pass `--param <name>=<value>` to change one of a day's parameters, such as
+// `--param rounds=100` for day 11. It can be repeated.
 
 use std::env;
 use std::process;
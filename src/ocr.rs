@@ -0,0 +1,109 @@
+//! Reading letters drawn in pixels
+//!
+//! Some puzzles answer with capital letters drawn on a screen of `#` (lit)
+//! and `.` (dark) pixels. The letters are all drawn in the same font, six
+//! pixels high and four wide, with a column of dark pixels after each one, so
+//! the screen can be cut into cells and each cell looked up in the font.
+
+use std::error::Error;
+use std::fmt;
+
+use crate::grid::Grid;
+
+/// Height of each letter, in pixels
+pub const HEIGHT: usize = 6;
+
+/// Width of each letter, in pixels, not counting the gap after it
+pub const WIDTH: usize = 4;
+
+/// The letters of the font, each drawn as its six rows run together
+const FONT: [(char, &str); 17] = [
+    ('A', ".##.#..##..######..##..#"),
+    ('B', "###.#..####.#..##..####."),
+    ('C', ".##.#..##...#...#..#.##."),
+    ('E', "#####...###.#...#...####"),
+    ('F', "#####...###.#...#...#..."),
+    ('G', ".##.#..##...#.###..#.###"),
+    ('H', "#..##..######..##..##..#"),
+    ('I', ".###..#...#...#...#..###"),
+    ('J', "..##...#...#...##..#.##."),
+    ('K', "#..##.#.##..#.#.#.#.#..#"),
+    ('L', "#...#...#...#...#...####"),
+    ('O', ".##.#..##..##..##..#.##."),
+    ('P', "###.#..##..####.#...#..."),
+    ('R', "###.#..##..####.#.#.#..#"),
+    ('S', ".####...#....##....####."),
+    ('U', "#..##..##..##..##..#.##."),
+    ('Z', "####...#..#..#..#...####"),
+];
+
+/// A letter on the screen which isn't in the font
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownLetter {
+    /// Position of the letter along the screen, counting from zero
+    pub index: usize,
+    /// The letter's pixels, one row per line
+    pub pixels: String,
+}
+
+impl fmt::Display for UnknownLetter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "letter {} isn't in the font:\n{}", self.index + 1, self.pixels)
+    }
+}
+
+impl Error for UnknownLetter {}
+
+/// Returns the letters drawn on `screen`, which must be `HEIGHT` pixels high
+pub fn read(screen: &Grid<char>) -> Result<String, UnknownLetter> {
+    assert_eq!(screen.nrow, HEIGHT, "letters are {} pixels high", HEIGHT);
+
+    screen
+        .tiles(HEIGHT, WIDTH + 1)
+        .enumerate()
+        .map(|(index, cell)| {
+            let glyph = (0..HEIGHT)
+                .flat_map(|i| (0..WIDTH).map(move |j| (i, j)))
+                .map(|(i, j)| cell.get_checked(i, j).unwrap_or('.'))
+                .collect::<String>();
+
+            FONT.iter()
+                .find(|(_, pixels)| *pixels == glyph)
+                .map(|&(letter, _)| letter)
+                .ok_or_else(|| UnknownLetter { index, pixels: _rows(&glyph) })
+        })
+        .collect()
+}
+
+/// Returns the pixels of a letter split back into rows
+fn _rows(glyph: &str) -> String {
+    glyph
+        .as_bytes()
+        .chunks(WIDTH)
+        .map(|row| String::from_utf8_lossy(row).into_owned())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_letters() {
+        let screen = "\
+###..#....####.####.#..#.#....###..###..
+#..#.#....#....#....#..#.#....#..#.#..#.
+#..#.#....###..###..#..#.#....#..#.###..
+###..#....#....#....#..#.#....###..#..#.
+#....#....#....#....#..#.#....#....#..#.
+#....####.####.#.....##..####.#....###..";
+        let screen = Grid::parse_with(screen, |c| c).unwrap();
+        assert_eq!(read(&screen), Ok("PLEFULPB".to_string()));
+
+        let smudged = Grid::parse_with("#.\n..\n..\n..\n..\n.#", |c| c).unwrap();
+        let error = read(&smudged).unwrap_err();
+        assert_eq!(error.index, 0);
+        assert_eq!(error.to_string(), "letter 1 isn't in the font:\n#...\n....\n....\n....\n....\n.#..");
+    }
+}
@@ -0,0 +1,385 @@
+//! Rendering results, either as text for people or as JSON and CSV records
+//! for scripts
+//!
+//! Each record describes one part of one day: the answer, plus statistics
+//! for the time spent parsing the input and solving the part, in nanoseconds.
+//! The parse timings are repeated for both parts of a day.
+
+use std::time::Duration;
+
+use crate::answers::{Check, Outcome};
+use crate::bench::{Bench, Stats};
+use crate::history::Comparison;
+use crate::picture::Picture;
+use crate::solution::{Answer, Report};
+
+/// How results should be written to stdout
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Text,
+    Json,
+    Csv,
+}
+
+impl Format {
+    /// Returns the format with the given name, as used on the command line
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "text" => Some(Format::Text),
+            "json" => Some(Format::Json),
+            "csv" => Some(Format::Csv),
+            _ => None,
+        }
+    }
+}
+
+/// Prints the results for a single day. `bench` is set when the timings
+/// come from repeated runs.
+pub fn print_day(format: Format, day: u8, report: &Report, bench: Option<&Bench>) {
+    match format {
+        Format::Text => _text_day(day, report, bench),
+        Format::Json => print!("{}", json(&[(day, report)])),
+        Format::Csv => print!("{}", csv(&[(day, report)])),
+    }
+}
+
+/// Prints the results for every day
+pub fn print_all(format: Format, reports: &[(u8, Report)], bench: Option<&Bench>) {
+    let reports: Vec<(u8, &Report)> = reports.iter().map(|(d, r)| (*d, r)).collect();
+
+    match format {
+        Format::Text => _text_summary(&reports, bench),
+        Format::Json => print!("{}", json(&reports)),
+        Format::Csv => print!("{}", csv(&reports)),
+    }
+}
+
+/// Prints a picture of how a part was solved. For JSON and CSV it goes to
+/// stderr so as not to mix with the records.
+pub fn print_picture(format: Format, part: u8, picture: &Picture) {
+    match format {
+        Format::Text => println!("\nPart {}:\n{}", part, picture),
+        Format::Json | Format::Csv => eprintln!("Part {}:\n{}", part, picture),
+    }
+}
+
+/// Prints the outcome of checking the answers. Only the answers which
+/// couldn't be confirmed are listed. For JSON and CSV these go to stderr so
+/// as not to mix with the records.
+pub fn print_checks(format: Format, checks: &[Check]) {
+    let unconfirmed = checks.iter().filter(|c| c.outcome != Outcome::Correct);
+
+    match format {
+        Format::Text => {
+            println!();
+            for check in unconfirmed {
+                println!("{}", check);
+            }
+
+            let count = |f: fn(&Outcome) -> bool| checks.iter().filter(|c| f(&c.outcome)).count();
+            println!(
+                "Checked {} answers: {} correct, {} wrong, {} unknown",
+                checks.len(),
+                count(|o| *o == Outcome::Correct),
+                count(|o| matches!(o, Outcome::Wrong { .. })),
+                count(|o| *o == Outcome::Unknown),
+            );
+        },
+        Format::Json | Format::Csv => {
+            for check in unconfirmed {
+                eprintln!("{}", check);
+            }
+        }
+    }
+}
+
+/// Prints the steps which were slower than in the benchmark history. As with
+/// the checks, these go to stderr for JSON and CSV.
+pub fn print_regressions(format: Format, comparison: &Comparison, threshold: f64) {
+    match format {
+        Format::Text => {
+            println!(
+                "\nCompared {} steps with the benchmark history: {} were more than {}% slower",
+                comparison.compared,
+                comparison.regressions.len(),
+                threshold,
+            );
+            for regression in &comparison.regressions {
+                println!("  - {}", regression);
+            }
+        },
+        Format::Json | Format::Csv => {
+            for regression in &comparison.regressions {
+                eprintln!("{}", regression);
+            }
+        }
+    }
+}
+
+// Text ------------------------------------------------------------------------
+
+fn _text_day(day: u8, report: &Report, bench: Option<&Bench>) {
+    match report.answers.as_slice() {
+        [answer] => {
+            println!("\nRunning day {:02}, part {}...", day, answer.part);
+            _text_answer("Solution", &answer.value);
+        },
+        answers => {
+            println!("\nRunning day {:02}...", day);
+            for (answer, label) in answers.iter().zip(["Part one", "Part two"]) {
+                _text_answer(label, &answer.value);
+            }
+        }
+    }
+
+    // Pair each timing with the step it belongs to
+    let steps: Vec<(String, &Stats)> = std::iter::once((String::from("parse"), &report.parse))
+        .chain(report.answers.iter().map(|a| (format!("part {}", a.part), &a.elapsed)))
+        .collect();
+
+    match bench {
+        Some(bench) => _text_stats(&steps, bench),
+        None => {
+            let elapsed: Vec<String> = steps
+                .iter()
+                .map(|(step, stats)| format!("{} {}", step, _micros(stats.median)))
+                .collect();
+            println!("  - Elapsed: {}", elapsed.join(", "));
+        }
+    }
+}
+
+/// Prints an answer, putting answers which span several lines underneath
+/// their label
+fn _text_answer(label: &str, value: &str) {
+    if value.contains('\n') {
+        println!("  - {}:", label);
+        for line in value.lines() {
+            println!("    {}", line);
+        }
+    } else {
+        println!("  - {}: {}", label, value);
+    }
+}
+
+/// Prints statistics for the time taken by each step of a solution
+fn _text_stats(steps: &[(String, &Stats)], bench: &Bench) {
+    println!("  - Timings over {}:", _runs(bench));
+    println!("    {:<8}{:>12}{:>12}{:>12}{:>12}", "", "min", "median", "mean", "stddev");
+
+    for (step, stats) in steps {
+        println!(
+            "    {:<8}{:>12}{:>12}{:>12}{:>12}",
+            step,
+            _micros(stats.min),
+            _micros(stats.median),
+            _micros(stats.mean),
+            _micros(stats.stddev),
+        );
+    }
+}
+
+/// Prints the answers and timings for each day, with a row of totals
+fn _text_summary(reports: &[(u8, &Report)], bench: Option<&Bench>) {
+    let header = ["Day", "Part one", "Part two", "Parse", "Part 1", "Part 2", "Total"];
+
+    // Answers spanning several lines don't fit in a table, so they're
+    // printed after it
+    let mut footnotes = Vec::new();
+
+    let mut rows: Vec<Vec<String>> = reports
+        .iter()
+        .map(|&(day, report)| {
+            let mut row = vec![format!("{:02}", day)];
+            for answer in &report.answers {
+                if answer.value.contains('\n') {
+                    footnotes.push((day, answer));
+                    row.push(String::from("(see below)"));
+                } else {
+                    row.push(answer.value.clone());
+                }
+            }
+            row.push(_micros(report.parse.median));
+            row.extend(report.answers.iter().map(|a| _micros(a.elapsed.median)));
+            row.push(_micros(report.elapsed()));
+            row
+        })
+        .collect();
+
+    // Sum each of the timing columns
+    let total = |f: &dyn Fn(&Report) -> Duration| _micros(reports.iter().map(|(_, r)| f(r)).sum());
+    rows.push(vec![
+        String::from("Total"),
+        String::new(),
+        String::new(),
+        total(&|r| r.parse.median),
+        total(&|r| r.answers[0].elapsed.median),
+        total(&|r| r.answers[1].elapsed.median),
+        total(&|r| r.elapsed()),
+    ]);
+
+    let widths: Vec<usize> = (0..header.len())
+        .map(|i| {
+            rows.iter()
+                .map(|row| row[i].chars().count())
+                .chain([header[i].len()])
+                .max()
+                .unwrap()
+        })
+        .collect();
+
+    // Answers are left-aligned, timings right-aligned
+    let format_row = |row: &[&str]| {
+        row.iter()
+            .zip(&widths)
+            .enumerate()
+            .map(|(i, (cell, &w))| match i {
+                0..=2 => format!("{:<w$}", cell),
+                _ => format!("{:>w$}", cell),
+            })
+            .collect::<Vec<String>>()
+            .join(" | ")
+    };
+    let rule = widths
+        .iter()
+        .map(|&w| "-".repeat(w))
+        .collect::<Vec<String>>()
+        .join("-+-");
+
+    if let Some(bench) = bench {
+        println!("\nMedian timings over {}", _runs(bench));
+    }
+    println!();
+    println!("{}", format_row(&header));
+    println!("{}", rule);
+    for (i, row) in rows.iter().enumerate() {
+        if i == rows.len() - 1 {
+            println!("{}", rule);
+        }
+        println!("{}", format_row(&row.iter().map(String::as_str).collect::<Vec<&str>>()));
+    }
+
+    for (day, answer) in footnotes {
+        println!("\nDay {:02}, part {}:", day, answer.part);
+        println!("{}", answer.value);
+    }
+}
+
+/// Describes the number of timed and warm-up runs
+fn _runs(bench: &Bench) -> String {
+    format!("{} runs, after {} warm-up runs", bench.runs, bench.warmup)
+}
+
+/// Returns a duration formatted in microseconds, to one decimal place
+fn _micros(duration: Duration) -> String {
+    format!("{:.1}µs", duration.as_secs_f64() * 1e6)
+}
+
+// JSON ------------------------------------------------------------------------
+
+/// Returns a JSON array holding a record for each part of each day
+pub fn json(reports: &[(u8, &Report)]) -> String {
+    let records: Vec<String> = reports
+        .iter()
+        .flat_map(|&(day, report)| {
+            report.answers.iter().map(move |answer| json_record(day, report, answer, None))
+        })
+        .collect();
+
+    match records.is_empty() {
+        true => String::from("[]\n"),
+        false => format!("[\n  {}\n]\n", records.join(",\n  ")),
+    }
+}
+
+/// Returns a JSON object describing one part of one day, optionally stamped
+/// with the time (in seconds since the Unix epoch) of the run
+pub fn json_record(day: u8, report: &Report, answer: &Answer, timestamp: Option<u64>) -> String {
+    let timestamp = match timestamp {
+        Some(t) => format!("\"timestamp\": {}, ", t),
+        None => String::new(),
+    };
+
+    format!(
+        "{{{}\"day\": {}, \"part\": {}, \"answer\": {}, \"parse_ns\": {}, \"solve_ns\": {}}}",
+        timestamp,
+        day,
+        answer.part,
+        _json_string(&answer.value),
+        _json_stats(&report.parse),
+        _json_stats(&answer.elapsed),
+    )
+}
+
+fn _json_stats(stats: &Stats) -> String {
+    format!(
+        "{{\"min\": {}, \"median\": {}, \"mean\": {}, \"stddev\": {}}}",
+        stats.min.as_nanos(),
+        stats.median.as_nanos(),
+        stats.mean.as_nanos(),
+        stats.stddev.as_nanos(),
+    )
+}
+
+/// Returns a string as a quoted JSON string literal
+fn _json_string(s: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+// CSV -------------------------------------------------------------------------
+
+const CSV_HEADER: &str = "day,part,answer,\
+    parse_min_ns,parse_median_ns,parse_mean_ns,parse_stddev_ns,\
+    solve_min_ns,solve_median_ns,solve_mean_ns,solve_stddev_ns";
+
+/// Returns a CSV table, with a header, holding a row for each part of each day
+pub fn csv(reports: &[(u8, &Report)]) -> String {
+    let mut table = format!("{}\n", CSV_HEADER);
+
+    for &(day, report) in reports {
+        for answer in &report.answers {
+            table.push_str(&format!(
+                "{},{},{},{},{}\n",
+                day,
+                answer.part,
+                _csv_field(&answer.value),
+                _csv_stats(&report.parse),
+                _csv_stats(&answer.elapsed),
+            ));
+        }
+    }
+
+    table
+}
+
+fn _csv_stats(stats: &Stats) -> String {
+    format!(
+        "{},{},{},{}",
+        stats.min.as_nanos(),
+        stats.median.as_nanos(),
+        stats.mean.as_nanos(),
+        stats.stddev.as_nanos(),
+    )
+}
+
+/// Returns a field quoted as RFC 4180 asks, if it needs quoting at all
+fn _csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
@@ -1,19 +1,290 @@
-use std::str;
-
-/// Returns a vector containing all the unsigned integers in the string
-pub fn parse_u32(s: &str) -> Vec<u32> {
-    s.as_bytes()
-        .split(|b| !b.is_ascii_digit())
-        .filter(|x| !x.is_empty())
-        .map(|x| str::from_utf8(x).unwrap().parse::<u32>().unwrap())
-        .collect()
-}
-
-/// Returns a vector containing all the unsigned integers in the string
-pub fn parse_i32(s: &str) -> Vec<i32> {
-    s.as_bytes()
-        .split(|b| !(*b == b'-' || b.is_ascii_digit()))
-        .filter(|x| !(x.is_empty() || *x == [b'-']))
-        .map(|x| str::from_utf8(x).unwrap().parse::<i32>().unwrap())
-        .collect()
-}
\ No newline at end of file
+use std::any::type_name;
+use std::borrow::Cow;
+use std::marker::PhantomData;
+use std::num::{IntErrorKind, ParseIntError};
+use std::str::FromStr;
+
+use crate::error::ParseError;
+
+/// Returns the input with CRLF line endings converted to LF and any trailing
+/// newlines removed, so that parsers only ever need to deal with `'\n'`
+pub fn normalise(input: &str) -> Cow<'_, str> {
+    let input = input.trim_end_matches(['\r', '\n']);
+
+    if input.contains('\r') {
+        Cow::Owned(input.replace("\r\n", "\n"))
+    } else {
+        Cow::Borrowed(input)
+    }
+}
+
+/// Returns the blocks of a normalised input which are separated by blank lines
+pub fn blocks(input: &str) -> impl Iterator<Item = &str> {
+    input.split("\n\n")
+}
+
+/// Returns an iterator over the integers in `s`, a slice of `input`. A `-`
+/// just before a number is taken as its sign, unless it joins the number to a
+/// word or another number, as in `x-1` or `2-4`.
+///
+/// Each integer which doesn't fit in a `T`, or which is negative when `T` is
+/// unsigned, is an error.
+pub fn ints<'a, T: FromStr<Err = ParseIntError>>(input: &'a str, s: &'a str) -> Ints<'a, T> {
+    Ints { input, s, pos: 0, _type: PhantomData }
+}
+
+/// Returns the integers in `s`, a slice of `input`, which must hold exactly `N`
+pub fn ints_n<T, const N: usize>(input: &str, s: &str) -> Result<[T; N], ParseError>
+where
+    T: FromStr<Err = ParseIntError>,
+{
+    let integers = ints(input, s).collect::<Result<Vec<T>, ParseError>>()?;
+
+    integers
+        .try_into()
+        .map_err(|_| ParseError::at(input, s, format!("{} integers", N)))
+}
+
+pub struct Ints<'a, T> {
+    input: &'a str,
+    s: &'a str,
+    pos: usize,
+    _type: PhantomData<T>,
+}
+
+impl<'a, T: FromStr<Err = ParseIntError>> Ints<'a, T> {
+    /// Returns the integers along with their byte offsets in `s`
+    pub fn with_offsets(mut self) -> impl Iterator<Item = Result<(usize, T), ParseError>> + 'a
+    where
+        T: 'a,
+    {
+        std::iter::from_fn(move || {
+            let token = self._next_token()?;
+            let offset = token.as_ptr() as usize - self.s.as_ptr() as usize;
+
+            Some(self._parse(token).map(|x| (offset, x)))
+        })
+    }
+
+    /// Returns the next integer, with its sign, and moves past it
+    fn _next_token(&mut self) -> Option<&'a str> {
+        let bytes = self.s.as_bytes();
+        let start = self.pos + bytes[self.pos..].iter().position(|b| b.is_ascii_digit())?;
+        let end = start + bytes[start..].iter().take_while(|b| b.is_ascii_digit()).count();
+        self.pos = end;
+
+        let signed = start > 0
+            && bytes[start - 1] == b'-'
+            && (start < 2 || !bytes[start - 2].is_ascii_alphanumeric());
+
+        Some(&self.s[(start - signed as usize)..end])
+    }
+
+    fn _parse(&self, token: &str) -> Result<T, ParseError> {
+        token.parse().map_err(|e: ParseIntError| {
+            let expected = match e.kind() {
+                IntErrorKind::PosOverflow | IntErrorKind::NegOverflow => {
+                    format!("an integer which fits in {}", type_name::<T>())
+                }
+                _ => "a non-negative integer".to_string(),
+            };
+
+            ParseError::at(self.input, token, expected)
+        })
+    }
+}
+
+impl<T: FromStr<Err = ParseIntError>> Iterator for Ints<'_, T> {
+    type Item = Result<T, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let token = self._next_token()?;
+
+        Some(self._parse(token))
+    }
+}
+
+/// Parses `token`, a slice of `input`, as an integer
+pub fn integer<T: FromStr>(input: &str, token: &str) -> Result<T, ParseError> {
+    token
+        .parse()
+        .map_err(|_| ParseError::at(input, token, "an integer"))
+}
+
+// Patterns --------------------------------------------------------------------
+
+/// A template for a line of puzzle text, such as `"move {n} from {from} to
+/// {to}"`. Text outside braces must appear exactly, while each `{name}` is a
+/// field which matches everything up to the text that follows it. Fields never
+/// run on past the end of a line, but a template may span several lines.
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    pieces: Vec<Piece>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Piece {
+    Text(&'static str),
+    Field(&'static str),
+}
+
+impl Pattern {
+    /// Returns the pattern for `template`. Panics if a brace is left open, or
+    /// if two fields are next to each other, since there'd be no telling
+    /// where one ends and the next begins.
+    pub fn new(template: &'static str) -> Self {
+        let mut pieces = Vec::new();
+        let mut rest = template;
+
+        while let Some(open) = rest.find('{') {
+            let close = open + rest[open..].find('}').expect("a field in the pattern isn't closed");
+            if open > 0 {
+                pieces.push(Piece::Text(&rest[..open]));
+            } else {
+                assert!(
+                    !matches!(pieces.last(), Some(Piece::Field(_))),
+                    "fields in a pattern must be separated by text"
+                );
+            }
+            pieces.push(Piece::Field(&rest[(open + 1)..close]));
+            rest = &rest[(close + 1)..];
+        }
+        if !rest.is_empty() {
+            pieces.push(Piece::Text(rest));
+        }
+
+        Pattern { pieces }
+    }
+
+    /// Matches `s`, a slice of `input`, against the pattern and returns the
+    /// text of each field
+    pub fn parse<'a>(&self, input: &'a str, s: &'a str) -> Result<Fields<'a>, ParseError> {
+        let mut fields = Vec::new();
+        let mut rest = s;
+
+        for (k, piece) in self.pieces.iter().enumerate() {
+            match *piece {
+                Piece::Text(text) => {
+                    rest = rest.strip_prefix(text).ok_or_else(|| {
+                        // Point past any leading whitespace which did match
+                        let indent = &text[..(text.len() - text.trim_start().len())];
+                        let token = rest.strip_prefix(indent).unwrap_or(rest);
+                        ParseError::at(input, token, _describe_text(text))
+                    })?;
+                }
+                Piece::Field(name) => {
+                    let line = &rest[..rest.find('\n').unwrap_or(rest.len())];
+                    let end = match self.pieces.get(k + 1) {
+                        Some(Piece::Text(next)) => {
+                            // Only the part of the text on this line can be searched for
+                            match next.split('\n').next().unwrap_or("") {
+                                "" => line.len(),
+                                first => line
+                                    .find(first)
+                                    .ok_or_else(|| ParseError::after(input, line, _describe_text(next)))?,
+                            }
+                        }
+                        _ => line.len(),
+                    };
+                    fields.push((name, &rest[..end]));
+                    rest = &rest[end..];
+                }
+            }
+        }
+
+        if !rest.is_empty() {
+            return Err(ParseError::at(input, rest, "the end of the line"));
+        }
+
+        Ok(Fields { input, fields })
+    }
+}
+
+/// Returns how a piece of text missing from the input is described in errors
+fn _describe_text(text: &str) -> String {
+    match text.trim() {
+        "" if text.contains('\n') => "a new line".to_string(),
+        "" => "a space".to_string(),
+        trimmed => format!("`{}`", trimmed),
+    }
+}
+
+/// The text matched by each field of a `Pattern`
+#[derive(Debug, Clone)]
+pub struct Fields<'a> {
+    input: &'a str,
+    fields: Vec<(&'static str, &'a str)>,
+}
+
+impl<'a> Fields<'a> {
+    /// Returns the text matched by the field called `name`, as a slice of the
+    /// input. Panics if the pattern has no such field.
+    pub fn slice(&self, name: &str) -> &'a str {
+        self.fields
+            .iter()
+            .find(|(field, _)| *field == name)
+            .map(|&(_, text)| text)
+            .unwrap_or_else(|| panic!("the pattern has no field `{}`", name))
+    }
+
+    /// Parses the text matched by the field called `name`
+    pub fn get<T: FromStr>(&self, name: &str) -> Result<T, ParseError> {
+        let token = self.slice(name);
+        let kind = type_name::<T>().rsplit("::").next().unwrap_or("value");
+        let expected = match kind.trim_start_matches(['i', 'u']) {
+            "8" | "16" | "32" | "64" | "128" | "size" => "an integer".to_string(),
+            _ => format!("a {}", kind),
+        };
+
+        token
+            .parse()
+            .map_err(|_| ParseError::at(self.input, token, format!("{} for `{}`", expected, name)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn integers() {
+        let line = "Sensor at x=-12, y=3: closest beacon is at x=2-4, y=--5 in a well-1 lit cave";
+        let found = ints::<i64>(line, line).collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(found, [-12, 3, 2, 4, -5, 1]);
+
+        let offsets = ints::<i64>(line, line).with_offsets().map(|x| x.unwrap().0).collect::<Vec<_>>();
+        assert_eq!(&line[offsets[0]..(offsets[0] + 3)], "-12");
+
+        let error = ints::<u32>(line, line).next().unwrap().unwrap_err();
+        assert_eq!((error.column, error.expected.as_str()), (13, "a non-negative integer"));
+        let error = ints::<u8>("cost 256", "cost 256").next().unwrap().unwrap_err();
+        assert_eq!(error.expected, "an integer which fits in u8");
+
+        assert_eq!(ints_n::<u64, 3>("1 2 3", "1 2 3").unwrap(), [1, 2, 3]);
+        assert_eq!(ints_n::<u64, 2>("1 2 3", "1 2 3").unwrap_err().expected, "2 integers");
+    }
+
+    #[test]
+    fn patterns() {
+        let pattern = Pattern::new("move {n} from {from} to {to}");
+        let fields = pattern.parse("move 12 from 3 to 1", "move 12 from 3 to 1").unwrap();
+        assert_eq!((fields.get::<u32>("n").unwrap(), fields.slice("from"), fields.get::<u8>("to").unwrap()), (12, "3", 1));
+
+        let input = "move 1 form 2 to 3\nmove x from 2 to 3";
+        let errors = input
+            .lines()
+            .map(|line| pattern.parse(input, line).and_then(|f| f.get::<u32>("n")).unwrap_err())
+            .map(|e| (e.line, e.column, e.expected))
+            .collect::<Vec<_>>();
+        assert_eq!(errors, [
+            (1, 19, "`from`".to_string()),
+            (2, 6, "an integer for `n`".to_string()),
+        ]);
+        let error = Pattern::new("x={x};").parse("x=1; y", "x=1; y").unwrap_err();
+        assert_eq!((error.column, error.expected.as_str()), (5, "the end of the line"));
+
+        let pattern = Pattern::new("Monkey {id}:\n  Items: {items}\n");
+        let fields = pattern.parse("Monkey 0:\n  Items: 1, 2\n", "Monkey 0:\n  Items: 1, 2\n").unwrap();
+        assert_eq!((fields.slice("id"), fields.slice("items")), ("0", "1, 2"));
+    }
+}
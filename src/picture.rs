@@ -0,0 +1,104 @@
+//! Pictures of how a puzzle was solved
+//!
+//! A picture is a grid of cells, each with a glyph for drawing it as text and
+//! a colour for drawing it as an image. Images are written as binary PPM
+//! files, which need nothing beyond the standard library and which most image
+//! viewers can open.
+
+use std::fmt;
+
+use crate::grid::Grid;
+
+/// Width and height of each cell of an image, in pixels
+pub const CELL_SIZE: usize = 8;
+
+pub type Colour = [u8; 3];
+
+/// The colour of the arrows drawn on images
+const INK: Colour = [0, 0, 0];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Cell {
+    pub glyph: char,
+    pub colour: Colour,
+}
+
+impl Cell {
+    pub fn new(glyph: char, colour: Colour) -> Self {
+        Cell { glyph, colour }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Picture {
+    cells: Grid<Cell>,
+}
+
+impl Picture {
+    /// Returns a picture with `nrow` rows of cells, given in row order
+    pub fn new(cells: Vec<Cell>, nrow: usize) -> Self {
+        Picture { cells: Grid::new(cells, nrow) }
+    }
+
+    /// Set the cell at location (i, j)
+    pub fn set(&mut self, i: usize, j: usize, cell: Cell) {
+        self.cells.set(i, j, cell);
+    }
+
+    /// Returns the picture as a binary PPM image. Each cell is drawn as a
+    /// square of its colour, with an arrow on top if its glyph is one of
+    /// `<`, `>`, `^` or `v`.
+    pub fn ppm(&self) -> Vec<u8> {
+        let (height, width) = (self.cells.nrow * CELL_SIZE, self.cells.ncol * CELL_SIZE);
+
+        let mut image = format!("P6\n{} {}\n255\n", width, height).into_bytes();
+        image.reserve(3 * width * height);
+
+        for y in 0..height {
+            for x in 0..width {
+                let cell = self.cells.get(y / CELL_SIZE, x / CELL_SIZE);
+                match _arrow(cell.glyph, y % CELL_SIZE, x % CELL_SIZE) {
+                    true => image.extend(INK),
+                    false => image.extend(cell.colour),
+                }
+            }
+        }
+
+        image
+    }
+}
+
+impl fmt::Display for Picture {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for i in 0..self.cells.nrow {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            for j in 0..self.cells.ncol {
+                write!(f, "{}", self.cells.get(i, j).glyph)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns true if the pixel at (row, col) of a cell is part of the arrow
+/// drawn for `glyph`. Each arrow is a chevron, rotated or reflected from `>`.
+fn _arrow(glyph: char, row: usize, col: usize) -> bool {
+    let last = CELL_SIZE - 1;
+    let (row, col) = match glyph {
+        '>' => (row, col),
+        '<' => (row, last - col),
+        'v' => (col, row),
+        '^' => (col, last - row),
+        _ => return false,
+    };
+
+    if !(1..last).contains(&row) {
+        return false;
+    }
+
+    let depth = (row - 1).min(last - 1 - row);
+    col == 2 + depth || col == 3 + depth
+}
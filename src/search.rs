@@ -0,0 +1,282 @@
+//! Shortest paths through graphs
+//!
+//! Each search is generic over the type of node and takes the graph as a
+//! function returning the neighbours of a node, so the same code serves grids,
+//! adjacency maps and graphs which are never built at all. Searches stop at
+//! the first node satisfying `goal`; pass `|_| false` to explore everything
+//! reachable from the start.
+//!
+//! Adapters which turn a `Grid` or a string-keyed adjacency map into a
+//! neighbour function are at the bottom of the file.
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::hash::Hash;
+use std::ops::Add;
+
+use crate::grid::Grid;
+
+type Point = (usize, usize);
+
+// Results ---------------------------------------------------------------------
+
+/// The cost of the cheapest path found to each node a search reached, along
+/// with the step taken into it
+#[derive(Debug, Clone)]
+pub struct Paths<N, C> {
+    costs: HashMap<N, C>,
+    parents: HashMap<N, N>,
+    /// The first node found which satisfied the goal, if any
+    pub goal: Option<N>,
+}
+
+impl<N: Clone + Eq + Hash, C: Copy> Paths<N, C> {
+    fn new(start: N, zero: C) -> Self {
+        Paths {
+            costs: HashMap::from([(start, zero)]),
+            parents: HashMap::new(),
+            goal: None,
+        }
+    }
+
+    /// Returns the cost of the cheapest path found to `node`
+    pub fn cost(&self, node: &N) -> Option<C> {
+        self.costs.get(node).copied()
+    }
+
+    /// Returns the cost of each node which was reached
+    pub fn costs(&self) -> &HashMap<N, C> {
+        &self.costs
+    }
+
+    /// Returns the nodes along the cheapest path found from the start to
+    /// `node`, including both ends
+    pub fn path(&self, node: &N) -> Option<Vec<N>> {
+        if !self.costs.contains_key(node) {
+            return None;
+        }
+
+        let mut path = vec![node.clone()];
+        while let Some(parent) = self.parents.get(&path[path.len() - 1]) {
+            path.push(parent.clone());
+        }
+        path.reverse();
+
+        Some(path)
+    }
+
+    /// Returns the cost of reaching the goal
+    pub fn goal_cost(&self) -> Option<C> {
+        self.goal.as_ref().and_then(|g| self.cost(g))
+    }
+
+    /// Returns the path from the start to the goal
+    pub fn goal_path(&self) -> Option<Vec<N>> {
+        self.goal.as_ref().and_then(|g| self.path(g))
+    }
+}
+
+// Searches --------------------------------------------------------------------
+
+/// Returns the number of steps to each node reachable from `start`, searching
+/// breadth first until a node satisfies `goal`
+pub fn bfs<N, I>(
+    start: N,
+    mut neighbours: impl FnMut(&N) -> I,
+    mut goal: impl FnMut(&N) -> bool,
+) -> Paths<N, usize>
+where
+    N: Clone + Eq + Hash,
+    I: IntoIterator<Item = N>,
+{
+    let mut paths = Paths::new(start.clone(), 0);
+    let mut queue = VecDeque::from([start]);
+
+    while let Some(node) = queue.pop_front() {
+        if goal(&node) {
+            paths.goal = Some(node);
+            break;
+        }
+
+        let steps = paths.costs[&node] + 1;
+        for next in neighbours(&node) {
+            if !paths.costs.contains_key(&next) {
+                paths.costs.insert(next.clone(), steps);
+                paths.parents.insert(next.clone(), node.clone());
+                queue.push_back(next);
+            }
+        }
+    }
+
+    paths
+}
+
+/// Returns the cheapest cost of reaching each node from `start`, where
+/// `neighbours` gives each neighbour along with the cost of stepping to it.
+/// The search stops once the cheapest node left to visit satisfies `goal`.
+pub fn dijkstra<N, C, I>(
+    start: N,
+    neighbours: impl FnMut(&N) -> I,
+    goal: impl FnMut(&N) -> bool,
+) -> Paths<N, C>
+where
+    N: Clone + Eq + Hash,
+    C: Copy + Ord + Default + Add<Output = C>,
+    I: IntoIterator<Item = (N, C)>,
+{
+    astar(start, neighbours, |_| C::default(), goal)
+}
+
+/// As `dijkstra`, but visiting nodes in order of their cost plus the
+/// `heuristic` estimate of the cost left to reach the goal. The heuristic
+/// must never overestimate, or the path found may not be the cheapest.
+pub fn astar<N, C, I>(
+    start: N,
+    mut neighbours: impl FnMut(&N) -> I,
+    mut heuristic: impl FnMut(&N) -> C,
+    mut goal: impl FnMut(&N) -> bool,
+) -> Paths<N, C>
+where
+    N: Clone + Eq + Hash,
+    C: Copy + Ord + Default + Add<Output = C>,
+    I: IntoIterator<Item = (N, C)>,
+{
+    let mut paths = Paths::new(start.clone(), C::default());
+
+    // The heap holds indices into `nodes`, so that nodes needn't be ordered
+    let mut queue = BinaryHeap::from([Reverse((heuristic(&start), C::default(), 0))]);
+    let mut nodes = vec![start];
+
+    while let Some(Reverse((_, cost, idx))) = queue.pop() {
+        let node = nodes[idx].clone();
+        if cost > paths.costs[&node] {
+            continue; // Already visited more cheaply
+        }
+
+        if goal(&node) {
+            paths.goal = Some(node);
+            break;
+        }
+
+        for (next, step) in neighbours(&node) {
+            let cost = cost + step;
+            if paths.costs.get(&next).is_some_and(|&c| c <= cost) {
+                continue;
+            }
+
+            paths.costs.insert(next.clone(), cost);
+            paths.parents.insert(next.clone(), node.clone());
+            queue.push(Reverse((cost + heuristic(&next), cost, nodes.len())));
+            nodes.push(next);
+        }
+    }
+
+    paths
+}
+
+// Adapters --------------------------------------------------------------------
+
+/// Returns the neighbour function of a grid, where it's possible to step
+/// between adjacent cells whenever `passable(from, to)` holds
+pub fn grid_steps<'a, T: Copy + PartialEq>(
+    grid: &'a Grid<T>,
+    passable: impl Fn(T, T) -> bool + 'a,
+) -> impl Fn(&Point) -> Vec<Point> + 'a {
+    move |&(i, j)| {
+        let from = grid.get(i, j);
+        grid.neighbours(i, j)
+            .filter(|&(ni, nj)| passable(from, grid.get(ni, nj)))
+            .collect()
+    }
+}
+
+/// Returns the weighted neighbour function of a grid, where `cost(from, to)`
+/// gives the cost of stepping between adjacent cells, if it's possible at all
+pub fn grid_costs<'a, T: Copy + PartialEq, C>(
+    grid: &'a Grid<T>,
+    cost: impl Fn(T, T) -> Option<C> + 'a,
+) -> impl Fn(&Point) -> Vec<(Point, C)> + 'a {
+    move |&(i, j)| {
+        let from = grid.get(i, j);
+        grid.neighbours(i, j)
+            .filter_map(|(ni, nj)| Some(((ni, nj), cost(from, grid.get(ni, nj))?)))
+            .collect()
+    }
+}
+
+/// Returns the neighbour function of a graph given as a map from each node to
+/// the nodes it leads to. Nodes missing from the map lead nowhere.
+pub fn graph_steps<'a>(graph: &'a HashMap<String, Vec<String>>) -> impl Fn(&&'a str) -> Vec<&'a str> {
+    move |node| {
+        graph
+            .get(*node)
+            .map(|next| next.iter().map(String::as_str).collect())
+            .unwrap_or_default()
+    }
+}
+
+/// Returns the weighted neighbour function of a graph given as a map from each
+/// node to the nodes it leads to and the cost of getting there
+pub fn graph_costs<'a, C: Copy>(
+    graph: &'a HashMap<String, Vec<(String, C)>>,
+) -> impl Fn(&&'a str) -> Vec<(&'a str, C)> {
+    move |node| {
+        graph
+            .get(*node)
+            .map(|next| next.iter().map(|(n, c)| (n.as_str(), *c)).collect())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAZE: &str = "\
+S.#...
+.##.#.
+...#..
+.#...E";
+
+    #[test]
+    fn bfs_through_maze() {
+        let grid = Grid::parse(MAZE).unwrap();
+        let (start, end) = (grid.find(b'S').unwrap(), grid.find(b'E').unwrap());
+
+        let paths = bfs(start, grid_steps(&grid, |_, to| to != b'#'), |&p| p == end);
+        assert_eq!(paths.goal_cost(), Some(8));
+
+        let path = paths.goal_path().unwrap();
+        assert_eq!((path[0], path[path.len() - 1]), (start, end));
+        assert_eq!(path.len(), 9);
+        assert!(path.windows(2).all(|w| w[0].0.abs_diff(w[1].0) + w[0].1.abs_diff(w[1].1) == 1));
+    }
+
+    /// Walls cost 10 to walk through rather than being impassable, so the
+    /// cheapest path still goes around them
+    #[test]
+    fn weighted_searches_agree() {
+        let grid = Grid::parse(MAZE).unwrap();
+        let (start, end) = (grid.find(b'S').unwrap(), grid.find(b'E').unwrap());
+        let cost = |_, to| Some(if to == b'#' { 10 } else { 1 });
+        let manhattan = |&(i, j): &Point| end.0.abs_diff(i) + end.1.abs_diff(j);
+
+        let d = dijkstra(start, grid_costs(&grid, cost), |&p| p == end);
+        let a = astar(start, grid_costs(&grid, cost), manhattan, |&p| p == end);
+        assert_eq!(d.goal_cost(), Some(8));
+        assert_eq!(a.goal_cost(), Some(8));
+    }
+
+    #[test]
+    fn graph_distances() {
+        let graph: HashMap<String, Vec<String>> = [("AA", "BB CC"), ("BB", "AA DD"), ("CC", "AA"), ("DD", "EE")]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.split(' ').map(String::from).collect()))
+            .collect();
+
+        let paths = bfs("AA", graph_steps(&graph), |_| false);
+        assert_eq!(paths.cost(&"EE"), Some(3));
+        assert_eq!(paths.path(&"EE").unwrap(), ["AA", "BB", "DD", "EE"]);
+        assert_eq!(paths.cost(&"ZZ"), None);
+    }
+}
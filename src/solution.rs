@@ -0,0 +1,102 @@
+//! A common interface for the daily puzzles
+
+use std::error::Error;
+use std::fmt::Display;
+use std::time::Duration;
+
+use crate::bench::{Bench, Stats};
+use crate::error::ParseError;
+use crate::picture::Picture;
+
+/// A puzzle solution, split into a parsing step and two parts which share
+/// the parsed input.
+pub trait Solution {
+    type Input;
+    type Output1: Display;
+    type Output2: Display;
+
+    /// Returns the puzzle input in the form expected by both parts
+    fn parse(input: &str) -> Result<Self::Input, ParseError>;
+
+    fn part1(input: &Self::Input) -> Solved<Self::Output1>;
+
+    fn part2(input: &Self::Input) -> Solved<Self::Output2>;
+
+    /// Returns a picture of how the given part was solved, for the puzzles
+    /// which have something worth looking at
+    fn render(_input: &Self::Input, _part: u8) -> Option<Picture> {
+        None
+    }
+
+    /// Sets a parameter given on the command line, such as a number of rounds,
+    /// or returns a description of why it can't be set
+    fn configure(_input: &mut Self::Input, name: &str, _value: &str) -> Result<(), String> {
+        Err(format!("there's no parameter `{}`", name))
+    }
+}
+
+/// The answer to one part of a puzzle, or why it couldn't be found
+pub type Solved<T> = Result<T, Box<dyn Error>>;
+
+/// Parameters given on the command line, as names and values
+pub type Params = [(String, String)];
+
+/// The answers to a puzzle, along with how long it took to find them
+pub struct Report {
+    pub parse: Stats,
+    pub answers: Vec<Answer>,
+}
+
+/// The answer to a single part of a puzzle
+pub struct Answer {
+    pub part: u8,
+    pub value: String,
+    pub elapsed: Stats,
+}
+
+impl Report {
+    /// Returns the total median time spent parsing and solving
+    pub fn elapsed(&self) -> Duration {
+        self.parse.median + self.answers.iter().map(|a| a.elapsed.median).sum::<Duration>()
+    }
+}
+
+/// Parses the input and returns the solution to each of the given parts,
+/// timing each step separately as many times as `bench` asks. Returns an error
+/// if any of the parts can't be solved.
+pub fn solve<S: Solution>(input: &str, params: &Params, parts: &[u8], bench: &Bench) -> Result<Report, Box<dyn Error>> {
+    let (parsed, parse) = bench.measure(|| S::parse(input));
+    let mut input = parsed?;
+    configure::<S>(&mut input, params)?;
+
+    let answers = parts
+        .iter()
+        .map(|&part| {
+            let (value, elapsed) = match part {
+                1 => bench.measure(|| S::part1(&input).map(|x| x.to_string())),
+                2 => bench.measure(|| S::part2(&input).map(|x| x.to_string())),
+                _ => panic!("part {} undefined", part),
+            };
+            let value = value.map_err(|e| format!("part {}: {}", part, e))?;
+
+            Ok(Answer { part, value, elapsed })
+        })
+        .collect::<Result<_, Box<dyn Error>>>()?;
+
+    Ok(Report { parse, answers })
+}
+
+/// Parses the input and returns a picture of how the given part was solved,
+/// if the solution can draw one
+pub fn render<S: Solution>(input: &str, params: &Params, part: u8) -> Result<Option<Picture>, Box<dyn Error>> {
+    let mut input = S::parse(input)?;
+    configure::<S>(&mut input, params)?;
+
+    Ok(S::render(&input, part))
+}
+
+fn configure<S: Solution>(input: &mut S::Input, params: &Params) -> Result<(), String> {
+    params
+        .iter()
+        .try_for_each(|(name, value)| S::configure(input, name, value))
+}